@@ -0,0 +1,312 @@
+//! # Cassette
+//!
+//! Record-and-replay transport for integration tests that run without
+//! network access. In record mode, every request is sent through the
+//! regular transport and each (path, request body) → (status, body) pair
+//! is written to a JSON file. In replay mode, the recorded responses are
+//! served from that file and a request that was not recorded fails with a
+//! [`TransportError::Other`] naming the request.
+//!
+//! Cassettes are set up through the client builders, which wrap the
+//! transport they would otherwise use:
+//!
+//! ```no_run
+//! use radix_client::cassette::CassetteMode;
+//! use radix_client::GatewayClientBuilder;
+//!
+//! let client = GatewayClientBuilder::new("https://mainnet.radixdlt.com".into())
+//!     .cassette(CassetteMode::from_env("tests/cassettes/indexer.json"))
+//!     .build_blocking()
+//!     .unwrap();
+//! ```
+
+use crate::transport::{
+    BoxFuture, HttpRequest, HttpResponse, TransportAsync, TransportBlocking,
+    TransportError,
+};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Environment variable that switches [`CassetteMode::from_env`]
+/// to recording.
+pub const RECORD_ENV_VAR: &str = "RADIX_CLIENT_RECORD";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests to the network and write them to the file,
+    /// replacing whatever it contained before.
+    Record(PathBuf),
+    /// Serve the responses recorded in the file, without network access.
+    Replay(PathBuf),
+}
+
+impl CassetteMode {
+    /// Records when the `RADIX_CLIENT_RECORD` environment variable is set
+    /// and replays otherwise, so CI runs against the checked in cassette.
+    pub fn from_env(file: impl Into<PathBuf>) -> Self {
+        match std::env::var_os(RECORD_ENV_VAR) {
+            Some(_) => CassetteMode::Record(file.into()),
+            None => CassetteMode::Replay(file.into()),
+        }
+    }
+}
+
+/// A single recorded request and its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub path: String,
+    /// The request body. Compared as JSON, so formatting does not matter.
+    pub request: serde_json::Value,
+    pub status: u16,
+    /// The response body as it was received.
+    pub response: String,
+}
+
+/// Transport that records to or replays from a cassette file.
+/// `T` is the transport that is recorded, which is unused when replaying.
+#[derive(Debug)]
+pub struct Cassette<T> {
+    file: PathBuf,
+    inner: Option<T>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    loaded: bool,
+    interactions: Vec<Interaction>,
+    /// Which recorded interactions have been replayed already.
+    replayed: Vec<bool>,
+}
+
+pub type CassetteAsync = Cassette<Arc<dyn TransportAsync>>;
+pub type CassetteBlocking = Cassette<Rc<dyn TransportBlocking>>;
+
+impl<T> Cassette<T> {
+    /// Sends requests through `inner` and records them to `file`.
+    pub fn record(file: impl Into<PathBuf>, inner: T) -> Self {
+        Cassette {
+            file: file.into(),
+            inner: Some(inner),
+            state: Mutex::new(State {
+                loaded: true,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Serves the responses recorded in `file`. The file is read
+    /// when the first request is made.
+    pub fn replay(file: impl Into<PathBuf>) -> Self {
+        Cassette {
+            file: file.into(),
+            inner: None,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// All interactions recorded so far, or loaded from the file.
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state.lock().unwrap().interactions.clone()
+    }
+
+    /// Appends the interaction and rewrites the file, so the cassette is
+    /// complete even if the process does not shut down cleanly.
+    fn record_interaction(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> Result<(), TransportError> {
+        let mut state = self.state.lock().unwrap();
+        state.interactions.push(Interaction {
+            path: request.path.clone(),
+            request: request_json(request),
+            status: response.status.as_u16(),
+            response: response.body.clone(),
+        });
+        if let Some(parent) = self.file.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| self.io_error(e))?;
+        }
+        let text = serde_json::to_string_pretty(&state.interactions)?;
+        std::fs::write(&self.file, text).map_err(|e| self.io_error(e))
+    }
+
+    /// Serves the first recorded response for the same path and body
+    /// that has not been replayed yet. Identical requests are answered
+    /// in the order they were recorded.
+    fn replay_interaction(
+        &self,
+        request: &HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.loaded {
+            let text = std::fs::read_to_string(&self.file)
+                .map_err(|e| self.io_error(e))?;
+            state.interactions =
+                crate::deserialize::from_str(&text).map_err(|e| {
+                    TransportError::Other(format!(
+                        "Invalid cassette {}: {}",
+                        self.file.display(),
+                        e
+                    ))
+                })?;
+            state.replayed = vec![false; state.interactions.len()];
+            state.loaded = true;
+        }
+        let body = request_json(request);
+        let State {
+            interactions,
+            replayed,
+            ..
+        } = &mut *state;
+        let index = interactions
+            .iter()
+            .zip(replayed.iter())
+            .position(|(interaction, replayed)| {
+                !replayed
+                    && interaction.path == request.path
+                    && interaction.request == body
+            })
+            .ok_or_else(|| {
+                TransportError::Other(format!(
+                    "No recorded response in cassette {} for {} with body {}",
+                    self.file.display(),
+                    request.path,
+                    request.body
+                ))
+            })?;
+        replayed[index] = true;
+        let interaction = &interactions[index];
+        Ok(HttpResponse {
+            status: StatusCode::from_u16(interaction.status).map_err(|e| {
+                TransportError::Other(format!("Invalid recorded status: {e}"))
+            })?,
+            headers: HeaderMap::new(),
+            body: interaction.response.clone(),
+        })
+    }
+
+    fn io_error(&self, error: std::io::Error) -> TransportError {
+        TransportError::Other(format!(
+            "Could not access cassette {}: {}",
+            self.file.display(),
+            error
+        ))
+    }
+}
+
+fn request_json(request: &HttpRequest) -> serde_json::Value {
+    serde_json::from_str(&request.body)
+        .unwrap_or_else(|_| serde_json::Value::String(request.body.clone()))
+}
+
+impl TransportAsync for CassetteAsync {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            match &self.inner {
+                Some(inner) => {
+                    let response = inner.send(request.clone()).await?;
+                    self.record_interaction(&request, &response)?;
+                    Ok(response)
+                }
+                None => self.replay_interaction(&request),
+            }
+        })
+    }
+}
+
+impl TransportBlocking for CassetteBlocking {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        match &self.inner {
+            Some(inner) => {
+                let response = inner.send(request.clone())?;
+                self.record_interaction(&request, &response)?;
+                Ok(response)
+            }
+            None => self.replay_interaction(&request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MockTransport;
+    use crate::{CoreClientBuilder, GatewayClientBuilder};
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("cassettes/gateway.json");
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("status/gateway-status", r#"{"first":true}"#);
+        mock.respond_ok("status/gateway-status", r#"{"first":false}"#);
+        let recording = CassetteBlocking::record(&file, mock);
+        for _ in 0..2 {
+            let request = HttpRequest {
+                url: "http://mock/status/gateway-status".to_string(),
+                path: "status/gateway-status".to_string(),
+                headers: HeaderMap::new(),
+                body: "null".to_string(),
+            };
+            recording.send(request).unwrap();
+        }
+
+        let client = GatewayClientBuilder::new("http://unused".to_string())
+            .cassette(CassetteMode::Replay(file))
+            .build_blocking()
+            .unwrap();
+        let first = client.post("status/gateway-status", ()).unwrap();
+        let second = client.post("status/gateway-status", ()).unwrap();
+        assert_eq!(first.0, r#"{"first":true}"#);
+        assert_eq!(second.0, r#"{"first":false}"#);
+        match client.post("status/gateway-status", ()) {
+            Err(TransportError::Other(message)) => {
+                assert!(message.contains("No recorded response"))
+            }
+            other => panic!("expected a cassette miss, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn async_clients_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("core.json");
+        let mock = Arc::new(MockTransport::new());
+        mock.respond(
+            "mempool/list",
+            StatusCode::BAD_REQUEST,
+            r#"{"code":400}"#,
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock)
+            .cassette(CassetteMode::Record(file.clone()))
+            .build_async()
+            .unwrap();
+        let body = serde_json::json!({ "network": "mainnet" });
+        let recorded = client.post("mempool/list", &body).await.unwrap();
+        assert_eq!(recorded.1, StatusCode::BAD_REQUEST);
+
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .cassette(CassetteMode::Replay(file))
+            .build_async()
+            .unwrap();
+        assert_eq!(client.post("mempool/list", &body).await.unwrap(), recorded);
+        let other = serde_json::json!({ "network": "stokenet" });
+        assert!(client.post("mempool/list", &other).await.is_err());
+    }
+}
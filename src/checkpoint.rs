@@ -0,0 +1,132 @@
+//! # Checkpoint
+//!
+//! Persists the progress of a managed transaction stream, so an indexer
+//! can resume where it left off after a restart. A [`CheckpointStore`]
+//! holds the state version of the last transaction that was handled.
+//!
+//! With [`CheckpointMode::AfterPage`] the stream stores its progress when
+//! the next page is requested, which is once the previous page has been
+//! handled. After a crash, the transactions of at most one page are
+//! delivered again. With [`CheckpointMode::OnAck`] nothing is stored until
+//! the consumer acknowledges a state version. Implementing the store on top
+//! of the database the consumer writes to, and acknowledging in the same
+//! database transaction, gives exactly-once processing.
+
+use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+pub trait CheckpointStore: Debug + Send + Sync {
+    /// The stored state version, or `None` when nothing was stored yet.
+    fn load(&self) -> io::Result<Option<u64>>;
+
+    fn save(&self, state_version: u64) -> io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckpointMode {
+    /// Store the progress whenever the next page is requested.
+    #[default]
+    AfterPage,
+    /// Only store the state versions passed to `ack`.
+    OnAck,
+}
+
+/// Stores the state version as text in a file. The file is replaced
+/// atomically, so a crash while saving leaves the previous checkpoint.
+#[derive(Debug)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileCheckpointStore { path: path.into() }
+    }
+
+    /// The file name with `.tmp` appended, so stores for `a.json`
+    /// and `a.txt` in the same directory do not share one.
+    fn temporary_path(&self) -> PathBuf {
+        let mut file_name =
+            self.path.file_name().unwrap_or_default().to_owned();
+        file_name.push(".tmp");
+        self.path.with_file_name(file_name)
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn load(&self) -> io::Result<Option<u64>> {
+        let text = match std::fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        text.trim().parse().map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid checkpoint in {}: {}", self.path.display(), e),
+            )
+        })
+    }
+
+    fn save(&self, state_version: u64) -> io::Result<()> {
+        let temporary = self.temporary_path();
+        let mut file = std::fs::File::create(&temporary)?;
+        file.write_all(format!("{}\n", state_version).as_bytes())?;
+        // The contents must be on disk before the rename replaces the
+        // previous checkpoint.
+        file.sync_all()?;
+        std::fs::rename(&temporary, &self.path)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointStore {
+    state_version: Mutex<Option<u64>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for InMemoryCheckpointStore {
+    fn load(&self) -> io::Result<Option<u64>> {
+        Ok(*self.state_version.lock().unwrap())
+    }
+
+    fn save(&self, state_version: u64) -> io::Result<()> {
+        *self.state_version.lock().unwrap() = Some(state_version);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_store_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileCheckpointStore::new(dir.path().join("indexer"));
+        assert_eq!(store.load().unwrap(), None);
+        store.save(42).unwrap();
+        store.save(43).unwrap();
+        assert_eq!(store.load().unwrap(), Some(43));
+
+        let json = FileCheckpointStore::new(dir.path().join("indexer.json"));
+        assert_eq!(json.temporary_path(), dir.path().join("indexer.json.tmp"));
+        json.save(7).unwrap();
+        assert_eq!(json.load().unwrap(), Some(7));
+        assert!(!json.temporary_path().exists());
+
+        std::fs::write(dir.path().join("indexer"), "garbage").unwrap();
+        assert_eq!(
+            store.load().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
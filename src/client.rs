@@ -4,113 +4,811 @@
 //! This only contains the structs and some low-level methods like a post request.
 //! Further impls on these structs may be provided by the modules responsible
 //! for the specific API endpoints.
+//!
+//! The clients can be created with `new`, which uses sensible defaults, or
+//! with a [`GatewayClientBuilder`](gateway::GatewayClientBuilder) /
+//! [`CoreClientBuilder`](core::CoreClientBuilder) when timeouts, headers,
+//! proxies or a pre-configured `reqwest` client are needed. The builders
+//! also take an optional [`RetryPolicy`] and [`RateLimiter`], which apply
+//! to every endpoint, and a custom [transport](crate::transport) that
+//! replaces `reqwest`, e.g. to run against canned responses in tests.
+//! Integration tests can record real responses to a
+//! [cassette](crate::cassette) and replay them later without network access.
+//! Fallback base URLs can be added for [failover](crate::failover), and a
+//! [ledger guard](crate::ledger_guard) keeps responses from going back in
+//! time when they do. API keys for hosted providers are configured as
+//! [credentials](crate::auth), which can be rotated without a rebuild.
+
+use crate::auth::{redact_url, CredentialStore, Credentials};
+use crate::cassette::{CassetteAsync, CassetteBlocking, CassetteMode};
+use crate::constants::DEFAULT_USER_AGENT;
+use crate::failover::Endpoints;
+use crate::ledger_guard::LedgerGuard;
+use crate::metrics::{ErrorVariant, MetricsHook, RequestMetrics};
+use crate::rate_limit::{
+    retry_after, RateLimitError, RateLimiter, DEFAULT_MAX_RETRY_AFTER,
+    MAX_RETRY_AFTER_WAITS,
+};
+use crate::retry::RetryPolicy;
+use crate::transport::{
+    json_headers, HttpRequest, TransportAsync, TransportBlocking,
+    TransportError,
+};
+use duplicate::duplicate_item;
+use maybe_async::{must_be_async, must_be_sync};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, field, info_span, trace, Instrument};
 
 #[cfg(feature = "gateway")]
 pub mod gateway {
-    use duplicate::duplicate_item;
-    use log::trace;
-    use maybe_async::{must_be_async, must_be_sync};
-    use serde::Serialize;
-    use std::rc::Rc;
-    use std::sync::Arc;
-
-    #[duplicate_item(
-        client_type                reqwest_client_type             smart_pointer;
-        [ GatewayClientAsync ]        [ reqwest::Client ]             [ Arc ];
-        [ GatewayClientBlocking ]     [ reqwest::blocking::Client ]   [ Rc];
-    )]
-    #[derive(Debug, Clone)]
-    pub struct client_type {
-        pub base_url: String,
-        pub client: smart_pointer<reqwest_client_type>,
-    }
-
-    #[duplicate_item(
-        client_type                reqwest_client_type           maybe_async_attr    smart_pointer;
-        [ GatewayClientAsync ]     [ reqwest::Client ]           [ must_be_async ]   [ Arc ];
-        [ GatewayClientBlocking ]  [ reqwest::blocking::Client ] [ must_be_sync ]    [ Rc ];
-    )]
-    impl client_type {
-        pub fn new(base_url: String) -> client_type {
-            client_type {
-                base_url,
-                client: smart_pointer::new(reqwest_client_type::new()),
+    pub use super::{
+        ClientBuildError, GatewayClientAsync, GatewayClientBlocking,
+        GatewayClientBuilder,
+    };
+}
+
+#[cfg(feature = "core")]
+pub mod core {
+    pub use super::{
+        ClientBuildError, CoreClientAsync, CoreClientBlocking,
+        CoreClientBuilder,
+    };
+}
+
+/// Why a client builder could not build a client.
+#[derive(Debug)]
+pub enum ClientBuildError {
+    /// The `reqwest` client could not be built.
+    Reqwest(reqwest::Error),
+    RateLimit(RateLimitError),
+}
+
+impl Display for ClientBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ClientBuildError::Reqwest(e) => {
+                write!(f, "Could not build the reqwest client: {}", e)
+            }
+            ClientBuildError::RateLimit(e) => {
+                write!(f, "Invalid rate limit: {}", e)
             }
         }
+    }
+}
 
-        #[maybe_async_attr]
-        pub async fn post<S: Serialize>(
-            &self,
-            path: &str,
-            body: S,
-        ) -> Result<(String, reqwest::StatusCode), reqwest::Error> {
-            let res = self
-                .client
-                .post(format!("{}/{}", &self.base_url, path))
-                .header(reqwest::header::ACCEPT, "application/json")
-                .header(reqwest::header::USER_AGENT, "reqwest/0.11.0")
-                .json(&body)
-                .send()
-                .await?;
-            let status = res.status();
-            let text = res.text().await?;
-            trace!("Status: {status} - Response: {text}");
-            Ok((text, status))
-        }
+impl Error for ClientBuildError {}
+
+impl From<reqwest::Error> for ClientBuildError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientBuildError::Reqwest(e)
     }
 }
 
-#[cfg(feature = "core")]
-pub mod core {
-    use duplicate::duplicate_item;
-    use log::trace;
-    use maybe_async::{must_be_async, must_be_sync};
-    use serde::Serialize;
-    use std::rc::Rc;
-    use std::sync::Arc;
-
-    #[duplicate_item(
-    client_type                reqwest_client_type             smart_pointer;
-    [ CoreClientAsync ]        [ reqwest::Client ]             [ Arc ];
-    [ CoreClientBlocking ]     [ reqwest::blocking::Client ]   [ Rc];
+impl From<RateLimitError> for ClientBuildError {
+    fn from(e: RateLimitError) -> Self {
+        ClientBuildError::RateLimit(e)
+    }
+}
+
+#[duplicate_item(
+    client_type                transport_trait         smart_pointer   feature_gate;
+    [ GatewayClientAsync ]     [ TransportAsync ]      [ Arc ]         [ cfg(feature = "gateway") ];
+    [ GatewayClientBlocking ]  [ TransportBlocking ]   [ Rc ]          [ cfg(feature = "gateway") ];
+    [ CoreClientAsync ]        [ TransportAsync ]      [ Arc ]         [ cfg(feature = "core") ];
+    [ CoreClientBlocking ]     [ TransportBlocking ]   [ Rc ]          [ cfg(feature = "core") ];
 )]
-    #[derive(Debug, Clone)]
-    pub struct client_type {
-        pub base_url: String,
-        pub client: smart_pointer<reqwest_client_type>,
+#[feature_gate]
+#[derive(Clone)]
+pub struct client_type {
+    pub base_url: String,
+    pub transport: smart_pointer<dyn transport_trait>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// Set when the client fails over between several base URLs,
+    /// in which case `base_url` is the preferred one.
+    pub endpoints: Option<Arc<Endpoints>>,
+    pub ledger_guard: Option<Arc<LedgerGuard>>,
+    /// Shared by all clones of the client.
+    pub credentials: Arc<CredentialStore>,
+    pub metrics: Option<Arc<dyn MetricsHook>>,
+}
+
+// The base URL is redacted, some providers put the API key in it.
+#[duplicate_item(
+    client_type                feature_gate;
+    [ GatewayClientAsync ]     [ cfg(feature = "gateway") ];
+    [ GatewayClientBlocking ]  [ cfg(feature = "gateway") ];
+    [ CoreClientAsync ]        [ cfg(feature = "core") ];
+    [ CoreClientBlocking ]     [ cfg(feature = "core") ];
+)]
+#[feature_gate]
+impl Debug for client_type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct(stringify!(client_type))
+            .field("base_url", &redact_url(&self.base_url))
+            .field("transport", &self.transport)
+            .field("retry_policy", &self.retry_policy)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("endpoints", &self.endpoints)
+            .field("ledger_guard", &self.ledger_guard)
+            .field("credentials", &self.credentials)
+            .field("metrics", &self.metrics)
+            .finish()
     }
+}
 
-    #[duplicate_item(
-    client_type                reqwest_client_type           maybe_async_attr    smart_pointer;
-    [ CoreClientAsync ]     [ reqwest::Client ]           [ must_be_async ]   [ Arc ];
-    [ CoreClientBlocking ]  [ reqwest::blocking::Client ] [ must_be_sync ]    [ Rc ];
+#[duplicate_item(
+    client_type                builder_type               build_fn           maybe_async_attr    sleep                     send_in_span                                                                                           feature_gate;
+    [ GatewayClientAsync ]     [ GatewayClientBuilder ]   [ build_async ]    [ must_be_async ]   [ tokio::time::sleep ]    [ self.send_with_retries(path, &body, &mut retries, &mut base_url).instrument(span.clone()).await ]    [ cfg(feature = "gateway") ];
+    [ GatewayClientBlocking ]  [ GatewayClientBuilder ]   [ build_blocking ] [ must_be_sync ]    [ std::thread::sleep ]    [ span.in_scope(|| self.send_with_retries(path, &body, &mut retries, &mut base_url)) ]                 [ cfg(feature = "gateway") ];
+    [ CoreClientAsync ]        [ CoreClientBuilder ]      [ build_async ]    [ must_be_async ]   [ tokio::time::sleep ]    [ self.send_with_retries(path, &body, &mut retries, &mut base_url).instrument(span.clone()).await ]    [ cfg(feature = "core") ];
+    [ CoreClientBlocking ]     [ CoreClientBuilder ]      [ build_blocking ] [ must_be_sync ]    [ std::thread::sleep ]    [ span.in_scope(|| self.send_with_retries(path, &body, &mut retries, &mut base_url)) ]                 [ cfg(feature = "core") ];
 )]
-    impl client_type {
-        pub fn new(base_url: String) -> client_type {
-            client_type {
+#[feature_gate]
+impl client_type {
+    /// Creates a client with default settings.
+    /// Use [`Self::builder`] for anything more specific.
+    pub fn new(base_url: String) -> client_type {
+        builder_type::new(base_url)
+            .build_fn()
+            .expect("Failed to build the default reqwest client")
+    }
+
+    pub fn builder(base_url: String) -> builder_type {
+        builder_type::new(base_url)
+    }
+
+    /// Posts the body to the given path. When the client has a retry
+    /// policy, transient failures are retried before a result is returned.
+    /// When the client has a rate limiter, requests wait for their turn and
+    /// a `429 Too Many Requests` with a `Retry-After` header is retried
+    /// once the requested time has passed. Without a rate limiter, the
+    /// retry policy waits at least that long. The request runs in a
+    /// `tracing` span and is reported to the metrics hook, if the client
+    /// has one, both with the base URL that served it.
+    #[maybe_async_attr]
+    pub async fn post<S: Serialize>(
+        &self,
+        path: &str,
+        body: S,
+    ) -> Result<(String, reqwest::StatusCode), TransportError> {
+        let span = info_span!(
+            "request",
+            path,
+            status = field::Empty,
+            latency_ms = field::Empty,
+            response_size = field::Empty,
+            retries = field::Empty,
+            base_url = field::Empty,
+        );
+        let started = Instant::now();
+        let mut retries = 0;
+        let mut base_url = None;
+        let result = send_in_span;
+        let latency = started.elapsed();
+        let base_url = base_url.map(redact_url);
+        span.record("latency_ms", latency.as_millis() as u64);
+        span.record("retries", retries);
+        if let Some(base_url) = &base_url {
+            span.record("base_url", base_url.as_str());
+        }
+        let (status, response_size) = match &result {
+            Ok((text, status)) => {
+                span.record("status", status.as_u16());
+                span.record("response_size", text.len());
+                (Some(*status), text.len())
+            }
+            Err(_) => (None, 0),
+        };
+        if let Some(metrics) = &self.metrics {
+            metrics.on_request(&RequestMetrics {
+                path: path.to_string(),
+                status,
+                latency,
+                response_size,
+                retries,
                 base_url,
-                client: smart_pointer::new(reqwest_client_type::new()),
+            });
+            if let Err(err) = &result {
+                metrics.on_error(path, err.variant());
+            }
+        }
+        result
+    }
+
+    /// Reports a failed API call to the metrics hook, if the client has
+    /// one. Used by the endpoints, for errors that occur after `post`.
+    pub(crate) fn observe<T, E: ErrorVariant>(
+        &self,
+        path: &str,
+        result: Result<T, E>,
+    ) -> Result<T, E> {
+        if let (Some(metrics), Err(err)) = (&self.metrics, &result) {
+            metrics.on_error(path, err.variant());
+        }
+        result
+    }
+
+    #[maybe_async_attr]
+    async fn send_with_retries<'a, S: Serialize>(
+        &'a self,
+        path: &str,
+        body: &S,
+        retries: &mut u32,
+        base_url: &mut Option<&'a str>,
+    ) -> Result<(String, reqwest::StatusCode), TransportError> {
+        let mut attempt = 1;
+        let mut rate_limited = 0;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                let delay = rate_limiter.acquire();
+                if !delay.is_zero() {
+                    trace!("Rate limited, waiting {delay:?} for {path}");
+                    sleep(delay).await;
+                }
             }
+            let result = self.send(path, body, base_url).await;
+            let retry_after = match &result {
+                Ok((_, status, retry_after))
+                    if *status == reqwest::StatusCode::TOO_MANY_REQUESTS =>
+                {
+                    *retry_after
+                }
+                _ => None,
+            };
+            if let (Some(rate_limiter), Some(delay)) =
+                (&self.rate_limiter, retry_after)
+            {
+                if rate_limited < MAX_RETRY_AFTER_WAITS
+                    && rate_limiter.pause_for(delay)
+                {
+                    debug!("Server asked to retry {path} after {delay:?}");
+                    rate_limited += 1;
+                    *retries += 1;
+                    continue;
+                }
+            }
+            let result = result.map(|(text, status, _)| (text, status));
+            let policy = match &self.retry_policy {
+                Some(policy) if attempt < policy.max_attempts => policy,
+                _ => return result,
+            };
+            let retry = match &result {
+                Ok((text, status)) => {
+                    policy.is_retriable_response(*status, text)
+                }
+                Err(err) => policy.is_retriable_error(err),
+            };
+            if !retry {
+                return result;
+            }
+            // A `Retry-After` the rate limiter did not wait for.
+            let delay = match retry_after {
+                Some(delay) if delay > DEFAULT_MAX_RETRY_AFTER => {
+                    return result
+                }
+                Some(delay) => policy.backoff(attempt).max(delay),
+                None => policy.backoff(attempt),
+            };
+            debug!(
+                "Attempt {attempt} for {path} failed, retrying in {delay:?}"
+            );
+            sleep(delay).await;
+            attempt += 1;
+            *retries += 1;
         }
+    }
 
-        #[maybe_async_attr]
-        pub async fn post<S: Serialize>(
-            &self,
-            path: &str,
-            body: S,
-        ) -> Result<(String, reqwest::StatusCode), reqwest::Error> {
-            let res = self
-                .client
-                .post(format!("{}/{}", &self.base_url, path))
-                .header(reqwest::header::ACCEPT, "application/json")
-                .header(reqwest::header::USER_AGENT, "reqwest/0.11.0")
-                .json(&body)
-                .send()
-                .await?;
-            let status = res.status();
-            let text = res.text().await?;
-            trace!("Status: {status} - Response: {text}");
-            Ok((text, status))
+    /// Rotates the credentials of this client and all of its clones.
+    pub fn set_credentials(&self, credentials: Credentials) {
+        self.credentials.set(credentials)
+    }
+
+    pub fn clear_credentials(&self) {
+        self.credentials.clear()
+    }
+
+    /// Sends a request to the first endpoint that does not fail and sets
+    /// `served_by` to its base URL. Also returns the `Retry-After` header,
+    /// if the response had one.
+    #[maybe_async_attr]
+    async fn send<'a, S: Serialize>(
+        &'a self,
+        path: &str,
+        body: &S,
+        served_by: &mut Option<&'a str>,
+    ) -> Result<(String, reqwest::StatusCode, Option<Duration>), TransportError>
+    {
+        let endpoints = match &self.endpoints {
+            Some(endpoints) => endpoints,
+            None => {
+                *served_by = Some(&self.base_url);
+                return self.send_to(&self.base_url, path, body).await;
+            }
+        };
+        let mut candidates = endpoints.candidates().into_iter().peekable();
+        loop {
+            let index = candidates.next().unwrap();
+            let base_url = endpoints.base_url(index);
+            *served_by = Some(base_url);
+            let result = self.send_to(base_url, path, body).await;
+            if !endpoints.record(index, &result) {
+                return result;
+            }
+            match candidates.peek() {
+                Some(next) => debug!(
+                    "{} failed for {path}, failing over to {}",
+                    redact_url(base_url),
+                    redact_url(endpoints.base_url(*next))
+                ),
+                None => return result,
+            }
         }
     }
+
+    #[maybe_async_attr]
+    async fn send_to<S: Serialize>(
+        &self,
+        base_url: &str,
+        path: &str,
+        body: &S,
+    ) -> Result<(String, reqwest::StatusCode, Option<Duration>), TransportError>
+    {
+        let mut body = serde_json::to_value(body)?;
+        if let Some(ledger_guard) = &self.ledger_guard {
+            ledger_guard.pin_request(&mut body);
+        }
+        let mut headers = json_headers();
+        self.credentials.apply(&mut headers)?;
+        let request = HttpRequest {
+            url: format!("{}/{}", base_url, path),
+            path: path.to_string(),
+            headers,
+            body: serde_json::to_string(&body)?,
+        };
+        let res = self.transport.send(request).await?;
+        let retry_after = retry_after(&res.headers);
+        // The base URL is left out, some providers put the API key in it.
+        trace!("{path} - Status: {} - Response: {}", res.status, res.body);
+        let result = Ok((res.body, res.status, retry_after));
+        match &self.ledger_guard {
+            Some(ledger_guard) => ledger_guard.check(&body, result),
+            None => result,
+        }
+    }
+}
+
+/// Builder for the async and blocking clients. Settings that configure the
+/// underlying `reqwest` client (timeouts, headers, user agent and proxies)
+/// are ignored when a pre-configured `reqwest` client or a custom transport
+/// is passed in.
+#[duplicate_item(
+    builder_type               feature_gate;
+    [ GatewayClientBuilder ]   [ cfg(feature = "gateway") ];
+    [ CoreClientBuilder ]      [ cfg(feature = "core") ];
+)]
+#[feature_gate]
+#[derive(Clone)]
+pub struct builder_type {
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    default_headers: HeaderMap,
+    user_agent: String,
+    proxies: Vec<reqwest::Proxy>,
+    no_proxy: bool,
+    async_transport: Option<Arc<dyn TransportAsync>>,
+    blocking_transport: Option<Rc<dyn TransportBlocking>>,
+    retry_policy: Option<RetryPolicy>,
+    requests_per_second: Option<f64>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cassette: Option<CassetteMode>,
+    fallback_urls: Vec<String>,
+    endpoints: Option<Arc<Endpoints>>,
+    ledger_guard: Option<Arc<LedgerGuard>>,
+    credentials: Option<Arc<CredentialStore>>,
+    metrics: Option<Arc<dyn MetricsHook>>,
+}
+
+#[duplicate_item(
+    builder_type               feature_gate;
+    [ GatewayClientBuilder ]   [ cfg(feature = "gateway") ];
+    [ CoreClientBuilder ]      [ cfg(feature = "core") ];
+)]
+#[feature_gate]
+impl Debug for builder_type {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fallback_urls: Vec<_> = self
+            .fallback_urls
+            .iter()
+            .map(|url| redact_url(url))
+            .collect();
+        f.debug_struct(stringify!(builder_type))
+            .field("base_url", &redact_url(&self.base_url))
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("default_headers", &self.default_headers)
+            .field("user_agent", &self.user_agent)
+            .field("proxies", &self.proxies)
+            .field("no_proxy", &self.no_proxy)
+            .field("async_transport", &self.async_transport)
+            .field("blocking_transport", &self.blocking_transport)
+            .field("retry_policy", &self.retry_policy)
+            .field("requests_per_second", &self.requests_per_second)
+            .field("rate_limiter", &self.rate_limiter)
+            .field("cassette", &self.cassette)
+            .field("fallback_urls", &fallback_urls)
+            .field("endpoints", &self.endpoints)
+            .field("ledger_guard", &self.ledger_guard)
+            .field("credentials", &self.credentials)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+#[duplicate_item(
+    builder_type               feature_gate;
+    [ GatewayClientBuilder ]   [ cfg(feature = "gateway") ];
+    [ CoreClientBuilder ]      [ cfg(feature = "core") ];
+)]
+#[feature_gate]
+impl builder_type {
+    pub fn new(base_url: String) -> Self {
+        builder_type {
+            base_url,
+            timeout: None,
+            connect_timeout: None,
+            default_headers: HeaderMap::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxies: vec![],
+            no_proxy: false,
+            async_transport: None,
+            blocking_transport: None,
+            retry_policy: None,
+            requests_per_second: None,
+            rate_limiter: None,
+            cassette: None,
+            fallback_urls: vec![],
+            endpoints: None,
+            ledger_guard: None,
+            credentials: None,
+            metrics: None,
+        }
+    }
+
+    /// Total timeout for a request, from connecting until the
+    /// response body has been read.
+    pub fn timeout(mut self, value: Duration) -> Self {
+        self.timeout = Some(value);
+        self
+    }
+
+    pub fn connect_timeout(mut self, value: Duration) -> Self {
+        self.connect_timeout = Some(value);
+        self
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Replaces all headers that are sent with every request.
+    pub fn default_headers(mut self, value: HeaderMap) -> Self {
+        self.default_headers = value;
+        self
+    }
+
+    pub fn user_agent(mut self, value: &str) -> Self {
+        self.user_agent = value.to_string();
+        self
+    }
+
+    pub fn proxy(mut self, value: reqwest::Proxy) -> Self {
+        self.proxies.push(value);
+        self
+    }
+
+    /// Disables all proxies, including the ones picked up
+    /// from the environment.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Use an already configured client for the async flavour.
+    pub fn reqwest_client(mut self, value: reqwest::Client) -> Self {
+        self.async_transport = Some(Arc::new(value));
+        self
+    }
+
+    /// Use an already configured client for the blocking flavour.
+    pub fn reqwest_blocking_client(
+        mut self,
+        value: reqwest::blocking::Client,
+    ) -> Self {
+        self.blocking_transport = Some(Rc::new(value));
+        self
+    }
+
+    /// Send the requests of the async flavour through a custom transport.
+    pub fn transport_async(mut self, value: Arc<dyn TransportAsync>) -> Self {
+        self.async_transport = Some(value);
+        self
+    }
+
+    /// Send the requests of the blocking flavour through a custom transport.
+    pub fn transport_blocking(
+        mut self,
+        value: Rc<dyn TransportBlocking>,
+    ) -> Self {
+        self.blocking_transport = Some(value);
+        self
+    }
+
+    /// Retry transient failures of every endpoint according to the policy.
+    pub fn retry_policy(mut self, value: RetryPolicy) -> Self {
+        self.retry_policy = Some(value);
+        self
+    }
+
+    /// Limit the client, and all of its clones, to the given
+    /// amount of requests per second. Building the client fails
+    /// unless the rate is greater than zero.
+    pub fn rate_limit(mut self, requests_per_second: f64) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self.rate_limiter = None;
+        self
+    }
+
+    /// Use a rate limiter that may also be shared with other clients,
+    /// e.g. an async and a blocking client hitting the same endpoint.
+    pub fn rate_limiter(mut self, value: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(value);
+        self.requests_per_second = None;
+        self
+    }
+
+    /// Adds a base URL to fail over to when the ones before it fail.
+    pub fn fallback(mut self, base_url: String) -> Self {
+        self.fallback_urls.push(base_url);
+        self
+    }
+
+    /// Fail over between endpoints with custom health tracking, replacing
+    /// the base URL and fallbacks. May be shared with other clients.
+    pub fn endpoints(mut self, value: Arc<Endpoints>) -> Self {
+        self.endpoints = Some(value);
+        self
+    }
+
+    /// Reject responses at an older ledger state than seen before, or fail
+    /// over to a fallback for them. Clones of the client share the guard.
+    pub fn ledger_guard(mut self, value: Arc<LedgerGuard>) -> Self {
+        self.ledger_guard = Some(value);
+        self
+    }
+
+    /// Authenticate every request, e.g. with an API key of a hosted
+    /// provider. The credentials can be rotated on the built client.
+    pub fn credentials(mut self, value: Credentials) -> Self {
+        self.credentials = Some(Arc::new(CredentialStore::new(Some(value))));
+        self
+    }
+
+    /// Use credentials that are shared with other clients, so rotating
+    /// them once applies to all of them.
+    pub fn credential_store(mut self, value: Arc<CredentialStore>) -> Self {
+        self.credentials = Some(value);
+        self
+    }
+
+    /// Report every request and failed API call to the hook.
+    pub fn metrics(mut self, value: Arc<dyn MetricsHook>) -> Self {
+        self.metrics = Some(value);
+        self
+    }
+
+    /// Record all requests to a cassette file, or replay them from one.
+    /// When replaying, no transport is used at all.
+    pub fn cassette(mut self, value: CassetteMode) -> Self {
+        self.cassette = Some(value);
+        self
+    }
+}
+
+#[duplicate_item(
+    builder_type               client_type                reqwest_builder_type                   transport_field        build_fn           smart_pointer   cassette_type          feature_gate;
+    [ GatewayClientBuilder ]   [ GatewayClientAsync ]     [ reqwest::ClientBuilder ]             [ async_transport ]    [ build_async ]    [ Arc ]         [ CassetteAsync ]      [ cfg(feature = "gateway") ];
+    [ GatewayClientBuilder ]   [ GatewayClientBlocking ]  [ reqwest::blocking::ClientBuilder ]   [ blocking_transport ] [ build_blocking ] [ Rc ]          [ CassetteBlocking ]   [ cfg(feature = "gateway") ];
+    [ CoreClientBuilder ]      [ CoreClientAsync ]        [ reqwest::ClientBuilder ]             [ async_transport ]    [ build_async ]    [ Arc ]         [ CassetteAsync ]      [ cfg(feature = "core") ];
+    [ CoreClientBuilder ]      [ CoreClientBlocking ]     [ reqwest::blocking::ClientBuilder ]   [ blocking_transport ] [ build_blocking ] [ Rc ]          [ CassetteBlocking ]   [ cfg(feature = "core") ];
+)]
+#[feature_gate]
+impl builder_type {
+    pub fn build_fn(self) -> Result<client_type, ClientBuildError> {
+        let rate_limiter = match self.requests_per_second {
+            Some(requests_per_second) => {
+                Some(Arc::new(RateLimiter::new(requests_per_second)?))
+            }
+            None => self.rate_limiter,
+        };
+        let transport = match self.transport_field {
+            Some(transport) => transport,
+            None => {
+                let mut builder = reqwest_builder_type::new()
+                    .user_agent(self.user_agent)
+                    .default_headers(self.default_headers);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if self.no_proxy {
+                    builder = builder.no_proxy();
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                smart_pointer::new(builder.build()?)
+            }
+        };
+        let transport = match self.cassette {
+            Some(CassetteMode::Record(file)) => {
+                smart_pointer::new(cassette_type::record(file, transport))
+            }
+            Some(CassetteMode::Replay(file)) => {
+                smart_pointer::new(cassette_type::replay(file))
+            }
+            None => transport,
+        };
+        let endpoints = match self.endpoints {
+            Some(endpoints) => Some(endpoints),
+            None if self.fallback_urls.is_empty() => None,
+            None => {
+                let mut base_urls = vec![self.base_url.clone()];
+                base_urls.extend(self.fallback_urls);
+                Some(Arc::new(Endpoints::new(base_urls)))
+            }
+        };
+        let base_url = match &endpoints {
+            Some(endpoints) => endpoints.base_url(0).to_string(),
+            None => self.base_url,
+        };
+        Ok(client_type {
+            base_url,
+            transport,
+            retry_policy: self.retry_policy,
+            rate_limiter,
+            endpoints,
+            ledger_guard: self.ledger_guard,
+            credentials: self.credentials.unwrap_or_default(),
+            metrics: self.metrics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpResponse, MockTransport};
+    use reqwest::header::RETRY_AFTER;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn builders_produce_every_client_flavour() {
+        let gateway = GatewayClientBuilder::new("http://localhost".to_string())
+            .timeout(Duration::from_secs(5))
+            .connect_timeout(Duration::from_secs(1))
+            .user_agent("my-indexer/0.1")
+            .header(
+                HeaderName::from_static("x-custom"),
+                HeaderValue::from_static("value"),
+            )
+            .no_proxy();
+        assert!(gateway.clone().build_async().is_ok());
+        assert!(gateway.build_blocking().is_ok());
+
+        let core = CoreClientBuilder::new("http://localhost".to_string())
+            .reqwest_client(reqwest::Client::new())
+            .reqwest_blocking_client(reqwest::blocking::Client::new());
+        assert_eq!(
+            core.clone().build_async().unwrap().base_url,
+            "http://localhost"
+        );
+        assert!(core.build_blocking().is_ok());
+    }
+
+    #[test]
+    fn retries_transient_failures() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond("status/gateway-status", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("status/gateway-status", "{}");
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .retry_policy(
+                RetryPolicy::new(2).initial_backoff(Duration::from_millis(1)),
+            )
+            .build_blocking()
+            .unwrap();
+
+        let (text, status) = client.post("status/gateway-status", ()).unwrap();
+        assert_eq!((text.as_str(), status), ("{}", StatusCode::OK));
+        assert_eq!(mock.requests().len(), 2);
+        assert_eq!(mock.requests()[0].url, "http://mock/status/gateway-status");
+    }
+
+    #[tokio::test]
+    async fn honours_retry_after_when_rate_limited() {
+        let mock = Arc::new(MockTransport::new());
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("0"));
+        mock.respond_with(
+            "mempool/list",
+            HttpResponse {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                headers,
+                body: "{}".to_string(),
+            },
+        );
+        mock.respond_ok("mempool/list", "{}");
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .rate_limit(100.0)
+            .build_async()
+            .unwrap();
+
+        let (_, status) = client.post("mempool/list", ()).await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[test]
+    fn honours_retry_after_without_a_rate_limiter() {
+        let mock = Rc::new(MockTransport::new());
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("1"));
+        mock.respond_with(
+            "mempool/list",
+            HttpResponse {
+                status: StatusCode::TOO_MANY_REQUESTS,
+                headers,
+                body: "{}".to_string(),
+            },
+        );
+        mock.respond_ok("mempool/list", "{}");
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .retry_policy(
+                RetryPolicy::new(2).initial_backoff(Duration::from_millis(1)),
+            )
+            .build_blocking()
+            .unwrap();
+
+        let started = Instant::now();
+        let (_, status) = client.post("mempool/list", ()).unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_rate_limits() {
+        let error = GatewayClientBuilder::new("http://mock".to_string())
+            .rate_limit(0.0)
+            .build_blocking()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ClientBuildError::RateLimit(RateLimitError::InvalidRate(_))
+        ));
+    }
 }
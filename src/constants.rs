@@ -2,3 +2,7 @@ pub const PUBLIC_GATEWAY_URL: &str = "https://mainnet.radixdlt.com";
 
 pub const PUBLIC_CORE_URL: &str =
     "https://radix-mainnet.rpc.grove.city/v1/326002fc/core";
+
+/// User agent sent by clients that are not given a custom one.
+pub const DEFAULT_USER_AGENT: &str =
+    concat!("radix-client/", env!("CARGO_PKG_VERSION"));
@@ -1,3 +1,7 @@
+use crate::core::stream::proofs::ProofMismatch;
+use crate::metrics::ErrorVariant;
+use crate::stream_config::StreamConfigError;
+use crate::transport::TransportError;
 use serde::Deserialize;
 use std::error::Error;
 use std::fmt::Debug;
@@ -34,6 +38,12 @@ pub enum CoreApiError {
     },
     ClientError(CoreApiErrorResponse),
     ServerError(CoreApiErrorResponse),
+    /// The request failed in a custom transport or could not be serialized.
+    Transport(TransportError),
+    InvalidStream(StreamConfigError),
+    /// A transaction stream page had a proof that does not match
+    /// its transactions.
+    ProofMismatch(ProofMismatch),
     Unknown,
 }
 
@@ -55,6 +65,13 @@ impl std::fmt::Display for CoreApiError {
             CoreApiError::ServerError(e) => {
                 write!(f, "Server error: {:?}", e)
             }
+            CoreApiError::Transport(e) => write!(f, "{}", e),
+            CoreApiError::InvalidStream(e) => {
+                write!(f, "Invalid stream: {}", e)
+            }
+            CoreApiError::ProofMismatch(e) => {
+                write!(f, "Proof mismatch: {}", e)
+            }
             CoreApiError::Unknown => write!(f, "Unknown error"),
         }
     }
@@ -69,8 +86,39 @@ impl Debug for CoreApiError {
 
 impl Error for CoreApiError {}
 
+// The URL is dropped, some providers put the API key in it.
 impl From<reqwest::Error> for CoreApiError {
     fn from(e: reqwest::Error) -> Self {
-        CoreApiError::Network(e)
+        CoreApiError::Network(e.without_url())
+    }
+}
+
+impl From<TransportError> for CoreApiError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Network(e) => CoreApiError::Network(e),
+            e => CoreApiError::Transport(e),
+        }
+    }
+}
+
+impl From<StreamConfigError> for CoreApiError {
+    fn from(e: StreamConfigError) -> Self {
+        CoreApiError::InvalidStream(e)
+    }
+}
+
+impl ErrorVariant for CoreApiError {
+    fn variant(&self) -> &'static str {
+        match self {
+            CoreApiError::Network(_) => "Network",
+            CoreApiError::Parsing { .. } => "Parsing",
+            CoreApiError::ClientError(_) => "ClientError",
+            CoreApiError::ServerError(_) => "ServerError",
+            CoreApiError::Transport(_) => "Transport",
+            CoreApiError::InvalidStream(_) => "InvalidStream",
+            CoreApiError::ProofMismatch(_) => "ProofMismatch",
+            CoreApiError::Unknown => "Unknown",
+        }
     }
 }
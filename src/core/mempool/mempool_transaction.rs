@@ -1,12 +1,17 @@
-use self::core::{error::CoreApiError, match_response, models::*};
+use self::core::{
+    builder::{RequestBuilderAsync, RequestBuilderBlocking},
+    error::CoreApiError,
+    match_response,
+    models::*,
+};
 use crate::*;
 use duplicate::duplicate_item;
 use maybe_async::*;
 
 #[duplicate_item(
     client_type                 maybe_async_attr ;
-    [ GatewayClientAsync ]     [ must_be_async ];
-    [ GatewayClientBlocking ]  [ must_be_sync ];
+    [ CoreClientAsync ]     [ must_be_async ];
+    [ CoreClientBlocking ]  [ must_be_sync ];
   )]
 impl client_type {
     #[maybe_async_attr]
@@ -20,6 +25,94 @@ impl client_type {
             payload_hashes,
         };
         let (text, status) = self.post("mempool/transaction", request).await?;
-        match_response(text, status)
+        self.observe("mempool/transaction", match_response(text, status))
+    }
+}
+
+// builder
+
+#[duplicate_item(
+    request_type client_type ;
+    [ RequestBuilderAsync ] [ CoreClientAsync ] ;
+    [ RequestBuilderBlocking ] [ CoreClientBlocking ] ;
+)]
+impl client_type {
+    pub fn mempool_transaction_builder(
+        &self,
+        network: String,
+    ) -> request_type<GetMempoolTransactionRequest> {
+        let request = GetMempoolTransactionRequest {
+            network,
+            ..Default::default()
+        };
+        request_type {
+            client: self.clone(),
+            request,
+        }
+    }
+}
+
+#[duplicate_item(
+    builder_type maybe_async_attr;
+    [ RequestBuilderAsync ] [ must_be_async ];
+    [ RequestBuilderBlocking ] [ must_be_sync ];
+)]
+impl builder_type<GetMempoolTransactionRequest> {
+    pub fn payload_hash(&mut self, value: String) -> &mut Self {
+        self.request.payload_hashes.push(value);
+        self
+    }
+
+    pub fn payload_hashes(&mut self, value: Vec<String>) -> &mut Self {
+        self.request.payload_hashes.extend(value);
+        self
+    }
+
+    #[maybe_async_attr]
+    pub async fn fetch(
+        &self,
+    ) -> Result<GetMempoolTransaction200Response, CoreApiError> {
+        self.client
+            .mempool_transaction(
+                self.request.network.clone(),
+                self.request.payload_hashes.clone(),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use std::rc::Rc;
+
+    #[test]
+    fn posts_to_the_core_api() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok(
+            "mempool/transaction",
+            include_str!(
+                "../../../tests/fixtures/core/mempool_transaction.json"
+            ),
+        );
+        let client =
+            CoreClientBuilder::new("http://node:3333/core".to_string())
+                .transport_blocking(mock.clone())
+                .build_blocking()
+                .unwrap();
+        let response = client
+            .mempool_transaction_builder("mainnet".to_string())
+            .payload_hash("a".to_string())
+            .payload_hashes(vec!["b".to_string()])
+            .fetch()
+            .unwrap();
+
+        assert_eq!(response.count, 2);
+        let request = &mock.requests()[0];
+        assert!(request.url.starts_with("http://node:3333/core"));
+        let body: serde_json::Value =
+            serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["payload_hashes"], serde_json::json!(["a", "b"]));
     }
 }
@@ -1,2 +1,41 @@
 pub mod mempool_list;
 pub mod mempool_transaction;
+pub mod monitor;
+pub mod watcher;
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use serde_json::json;
+
+    /// A `mempool/list` response with the given payload hashes.
+    pub fn mempool_list(payload_hashes: &[&str]) -> String {
+        let contents: Vec<_> = payload_hashes
+            .iter()
+            .map(|hash| {
+                json!({
+                    "intent_hash": format!("intent_{hash}"),
+                    "intent_hash_bech32m": format!("txid_{hash}"),
+                    "payload_hash": hash,
+                    "payload_hash_bech32m": format!("notarizedtransaction_{hash}")
+                })
+            })
+            .collect();
+        json!({ "contents": contents }).to_string()
+    }
+
+    /// A `mempool/transaction` response with a payload
+    /// for each of the given payload hashes.
+    pub fn mempool_payloads(payload_hashes: &[&str]) -> String {
+        let payloads: Vec<_> = payload_hashes
+            .iter()
+            .map(|hash| {
+                json!({
+                    "hash": hash,
+                    "hash_bech32m": format!("notarizedtransaction_{hash}"),
+                    "hex": format!("4d{hash}")
+                })
+            })
+            .collect();
+        json!({ "count": payloads.len(), "payloads": payloads }).to_string()
+    }
+}
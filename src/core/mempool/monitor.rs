@@ -0,0 +1,710 @@
+//! # Mempool monitor
+//!
+//! Follows the transactions of a node from the moment they enter its
+//! mempool until they are committed or dropped. Every poll takes a
+//! snapshot of `mempool/list` with a [`watcher`](super::watcher), fetches
+//! the payloads of the transactions that appeared from `mempool/transaction`
+//! in batches, and follows the committed transactions on the Core
+//! `stream/transactions` endpoint. A transaction that leaves the mempool is
+//! reported as [`Removed`](MempoolMonitorEvent::Removed), then as
+//! [`Committed`](MempoolMonitorEvent::Committed) once it shows up on the
+//! ledger, or as [`Dropped`](MempoolMonitorEvent::Dropped) when it did not
+//! within `drop_after`.
+
+use crate::core::error::CoreApiError;
+use crate::core::mempool::watcher::*;
+use crate::core::models::*;
+use crate::core::stream::stream_client::*;
+use crate::transport::BoxFuture;
+use crate::{CoreClientAsync, CoreClientBlocking};
+use duplicate::duplicate_item;
+use futures_core::Stream;
+use maybe_async::{must_be_async, must_be_sync};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Number of committed transactions remembered while
+/// they may still be listed in the mempool.
+const COMMITTED_CAPACITY: usize = 10_000;
+
+/// How far behind the ledger tip the committed transaction stream may
+/// start. One page is fetched per poll, so a monitor that starts further
+/// behind would take long to report anything as committed.
+pub const MAX_STATE_VERSIONS_BEHIND: u64 = MAX_LIMIT_PER_PAGE as u64;
+
+#[derive(Debug, Clone)]
+pub enum MempoolMonitorEvent {
+    /// The transaction entered the mempool.
+    Added {
+        hashes: MempoolTransactionHashes,
+        payload: MempoolTransactionPayloads,
+    },
+    /// The transaction left the mempool. It is followed
+    /// by a `Committed` or a `Dropped` event.
+    Removed {
+        hashes: MempoolTransactionHashes,
+        payload: MempoolTransactionPayloads,
+    },
+    Committed {
+        hashes: MempoolTransactionHashes,
+        payload: MempoolTransactionPayloads,
+        state_version: u64,
+    },
+    /// The transaction left the mempool without being committed.
+    Dropped {
+        hashes: MempoolTransactionHashes,
+        payload: MempoolTransactionPayloads,
+    },
+}
+
+impl MempoolMonitorEvent {
+    pub fn hashes(&self) -> &MempoolTransactionHashes {
+        match self {
+            MempoolMonitorEvent::Added { hashes, .. }
+            | MempoolMonitorEvent::Removed { hashes, .. }
+            | MempoolMonitorEvent::Committed { hashes, .. }
+            | MempoolMonitorEvent::Dropped { hashes, .. } => hashes,
+        }
+    }
+
+    pub fn payload(&self) -> &MempoolTransactionPayloads {
+        match self {
+            MempoolMonitorEvent::Added { payload, .. }
+            | MempoolMonitorEvent::Removed { payload, .. }
+            | MempoolMonitorEvent::Committed { payload, .. }
+            | MempoolMonitorEvent::Dropped { payload, .. } => payload,
+        }
+    }
+}
+
+/// A transaction that is in the mempool, or left it and
+/// is waiting to be committed.
+#[derive(Debug, Clone)]
+struct Tracked {
+    hashes: MempoolTransactionHashes,
+    payload: MempoolTransactionPayloads,
+    removed_at: Option<Instant>,
+}
+
+type PayloadsResult =
+    Result<HashMap<String, MempoolTransactionPayloads>, CoreApiError>;
+
+/// The steps of a poll of the async monitor that are done. The mempool
+/// snapshot and the committed page are pending in the watcher and the
+/// committed stream themselves.
+#[derive(Default)]
+struct PollState {
+    changes: Option<Vec<MempoolEvent>>,
+    payloads: Option<BoxFuture<'static, PayloadsResult>>,
+    fetched: Option<HashMap<String, MempoolTransactionPayloads>>,
+}
+
+impl Debug for PollState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PollState")
+            .field("changes", &self.changes)
+            .field("fetched", &self.fetched.is_some())
+            .finish()
+    }
+}
+
+/// Besides polling once with `.next_events()`, the async monitor
+/// implements `futures::Stream` and the blocking one `Iterator`, yielding
+/// one event at a time and waiting `poll_interval` between polls. Errors
+/// are yielded as items, after which the monitor keeps polling.
+#[duplicate_item(
+    monitor_type                 watcher_type               stream_type                         poll_state_type ;
+    [ MempoolMonitorAsync ]      [ MempoolWatcherAsync ]    [ CoreTransactionStreamAsync ]      [ PollState ];
+    [ MempoolMonitorBlocking ]   [ MempoolWatcherBlocking ] [ CoreTransactionStreamBlocking ]   [ () ];
+)]
+#[derive(Debug)]
+pub struct monitor_type {
+    pub watcher: watcher_type,
+    pub committed_stream: stream_type,
+    pub batch_size: usize,
+    pub drop_after: Duration,
+    /// By payload hash.
+    tracked: HashMap<String, Tracked>,
+    /// State versions of the committed transactions that were not
+    /// removed from the mempool yet, by payload hash.
+    committed: HashMap<String, u64>,
+    committed_order: VecDeque<String>,
+    /// Changes of the mempool that could not be handled yet.
+    unhandled: Vec<MempoolEvent>,
+    events: VecDeque<MempoolMonitorEvent>,
+    polled: bool,
+    // The blocking monitor polls in place.
+    poll_state: poll_state_type,
+}
+
+#[duplicate_item(
+    monitor_type                 client_type              watcher_type               stream_type                        new_poll_state           maybe_async_attr ;
+    [ MempoolMonitorAsync ]      [ CoreClientAsync ]      [ MempoolWatcherAsync ]    [ CoreTransactionStreamAsync ]     [ PollState::default() ] [ must_be_async ];
+    [ MempoolMonitorBlocking ]   [ CoreClientBlocking ]   [ MempoolWatcherBlocking ] [ CoreTransactionStreamBlocking ]  [ () ]                   [ must_be_sync ];
+)]
+impl monitor_type {
+    /// Watches the mempool and follows the ledger from
+    /// `from_state_version`. Reads the ledger tip from the node first and
+    /// fails with [`StreamConfigError::TooFarBehind`] if the start is more
+    /// than [`MAX_STATE_VERSIONS_BEHIND`] behind it.
+    #[maybe_async_attr]
+    pub async fn new(
+        client: &client_type,
+        network: String,
+        from_state_version: u64,
+    ) -> Result<monitor_type, CoreApiError> {
+        let monitor =
+            Self::unchecked(client, network.clone(), from_state_version)?;
+        let ledger_tip = Self::ledger_tip(client, network).await?;
+        if ledger_tip.saturating_sub(from_state_version - 1)
+            > MAX_STATE_VERSIONS_BEHIND
+        {
+            return Err(CoreApiError::InvalidStream(
+                StreamConfigError::TooFarBehind {
+                    from_state_version,
+                    ledger_tip,
+                    max_behind: MAX_STATE_VERSIONS_BEHIND,
+                },
+            ));
+        }
+        Ok(monitor)
+    }
+
+    /// Watches the mempool and follows the ledger after its tip,
+    /// to only report transactions committed from now on. The tip
+    /// transaction is requested with the first page and filtered out.
+    #[maybe_async_attr]
+    pub async fn from_ledger_tip(
+        client: &client_type,
+        network: String,
+    ) -> Result<monitor_type, CoreApiError> {
+        let ledger_tip = Self::ledger_tip(client, network.clone()).await?;
+        let mut monitor = Self::unchecked(client, network, ledger_tip.max(1))?;
+        monitor.committed_stream.last_seen_state_version = ledger_tip;
+        Ok(monitor)
+    }
+
+    /// The state version of the ledger tip of the node.
+    #[maybe_async_attr]
+    async fn ledger_tip(
+        client: &client_type,
+        network: String,
+    ) -> Result<u64, CoreApiError> {
+        let response = client
+            .committed_transactions_builder(network, 1, 1)
+            .fetch()
+            .await?;
+        Ok(response.max_ledger_state_version)
+    }
+
+    fn unchecked(
+        client: &client_type,
+        network: String,
+        from_state_version: u64,
+    ) -> Result<monitor_type, StreamConfigError> {
+        Ok(monitor_type {
+            watcher: watcher_type::new(client, network.clone()),
+            committed_stream: stream_type::new(
+                client,
+                network,
+                from_state_version,
+                MAX_LIMIT_PER_PAGE,
+            )?,
+            batch_size: 100,
+            drop_after: Duration::from_secs(30),
+            tracked: HashMap::new(),
+            committed: HashMap::new(),
+            committed_order: VecDeque::new(),
+            unhandled: vec![],
+            events: VecDeque::new(),
+            polled: false,
+            poll_state: new_poll_state,
+        })
+    }
+
+    /// How many payloads are fetched per `mempool/transaction`
+    /// request. Defaults to 100.
+    pub fn batch_size(mut self, value: usize) -> Self {
+        self.batch_size = value.max(1);
+        self
+    }
+
+    /// How long a transaction that left the mempool may take to show
+    /// up on the ledger before it counts as dropped. Defaults to 30
+    /// seconds.
+    pub fn drop_after(mut self, value: Duration) -> Self {
+        self.drop_after = value;
+        self
+    }
+
+    /// How long to wait between polls, when used as
+    /// a `Stream` or `Iterator`. Defaults to one second.
+    pub fn poll_interval(mut self, value: Duration) -> Self {
+        self.watcher.poll_interval = value;
+        self
+    }
+
+    /// Turns the changes of the mempool, the payloads of the added
+    /// transactions and a page of committed transactions into events.
+    fn handle(
+        &mut self,
+        changes: &[MempoolEvent],
+        mut payloads: HashMap<String, MempoolTransactionPayloads>,
+        page: TransactionStream200ResponseBody,
+    ) -> Vec<MempoolMonitorEvent> {
+        let mut events = vec![];
+        for hashes in added(changes) {
+            let payload = payloads
+                .remove(&hashes.payload_hash)
+                .unwrap_or_else(|| missing_payload(&hashes));
+            events.push(MempoolMonitorEvent::Added {
+                hashes: hashes.clone(),
+                payload: payload.clone(),
+            });
+            self.tracked.insert(
+                hashes.payload_hash.clone(),
+                Tracked {
+                    hashes,
+                    payload,
+                    removed_at: None,
+                },
+            );
+        }
+
+        for transaction in page.transactions {
+            if let LedgerTransactionType::User(user) =
+                transaction.ledger_transaction
+            {
+                self.remember_committed(
+                    user.notarized_transaction.hash,
+                    transaction.resultant_state_identifiers.state_version,
+                );
+            }
+        }
+
+        let now = Instant::now();
+        for change in changes {
+            if let MempoolEvent::Removed(hashes) = change {
+                let hashes = hashes.clone();
+                let tracked = self
+                    .tracked
+                    .entry(hashes.payload_hash.clone())
+                    .or_insert_with(|| Tracked {
+                        payload: missing_payload(&hashes),
+                        hashes,
+                        removed_at: None,
+                    });
+                tracked.removed_at = Some(now);
+                events.push(MempoolMonitorEvent::Removed {
+                    hashes: tracked.hashes.clone(),
+                    payload: tracked.payload.clone(),
+                });
+            }
+        }
+        events.extend(self.resolve(now));
+        events
+    }
+
+    /// Fetches the payloads of the transactions, `batch_size` at a time.
+    #[maybe_async_attr]
+    async fn fetch_payloads(
+        client: client_type,
+        network: String,
+        added: Vec<MempoolTransactionHashes>,
+        batch_size: usize,
+    ) -> PayloadsResult {
+        let mut payloads = HashMap::new();
+        for batch in added.chunks(batch_size) {
+            let payload_hashes =
+                batch.iter().map(|hashes| hashes.payload_hash.clone());
+            let response = client
+                .mempool_transaction(network.clone(), payload_hashes.collect())
+                .await?;
+            for payload in response.payloads {
+                payloads.insert(payload.hash.clone(), payload);
+            }
+        }
+        Ok(payloads)
+    }
+
+    fn remember_committed(&mut self, payload_hash: String, state_version: u64) {
+        if self.committed_order.len() == COMMITTED_CAPACITY {
+            if let Some(oldest) = self.committed_order.pop_front() {
+                self.committed.remove(&oldest);
+            }
+        }
+        self.committed_order.push_back(payload_hash.clone());
+        self.committed.insert(payload_hash, state_version);
+    }
+
+    /// Reports the transactions that left the mempool and were
+    /// committed since, or did not show up in time.
+    fn resolve(&mut self, now: Instant) -> Vec<MempoolMonitorEvent> {
+        let mut resolved: Vec<_> = self
+            .tracked
+            .iter()
+            .filter_map(|(payload_hash, tracked)| {
+                let removed_at = tracked.removed_at?;
+                let state_version = self.committed.get(payload_hash).copied();
+                (state_version.is_some()
+                    || now.duration_since(removed_at) >= self.drop_after)
+                    .then(|| (payload_hash.clone(), state_version))
+            })
+            .collect();
+        resolved.sort();
+        resolved
+            .into_iter()
+            .map(|(payload_hash, state_version)| {
+                let Tracked {
+                    hashes, payload, ..
+                } = self.tracked.remove(&payload_hash).unwrap();
+                match state_version {
+                    Some(state_version) => {
+                        self.committed.remove(&payload_hash);
+                        MempoolMonitorEvent::Committed {
+                            hashes,
+                            payload,
+                            state_version,
+                        }
+                    }
+                    None => MempoolMonitorEvent::Dropped { hashes, payload },
+                }
+            })
+            .collect()
+    }
+}
+
+impl MempoolMonitorAsync {
+    /// Polls the mempool and the ledger once and returns what happened
+    /// since the last poll. After an error, the changes of the mempool
+    /// are handled again on the next poll.
+    pub async fn next_events(
+        &mut self,
+    ) -> Result<Vec<MempoolMonitorEvent>, CoreApiError> {
+        std::future::poll_fn(|cx| self.poll_events(cx, None)).await
+    }
+
+    /// Polls for the events of the next poll, which starts after `delay`:
+    /// the mempool snapshot, then the payloads of the added transactions,
+    /// then a page of committed transactions.
+    fn poll_events(
+        &mut self,
+        cx: &mut Context<'_>,
+        delay: Option<Duration>,
+    ) -> Poll<Result<Vec<MempoolMonitorEvent>, CoreApiError>> {
+        if self.poll_state.changes.is_none() {
+            let mut changes = match self.watcher.poll_changes(cx, delay) {
+                Poll::Ready(Ok(changes)) => changes,
+                Poll::Ready(Err(error)) => {
+                    self.polled = true;
+                    return Poll::Ready(Err(error));
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            changes.splice(0..0, std::mem::take(&mut self.unhandled));
+            self.poll_state.changes = Some(changes);
+        }
+        if self.poll_state.fetched.is_none() {
+            let changes = self.poll_state.changes.as_deref().unwrap();
+            let pending = self.poll_state.payloads.get_or_insert_with(|| {
+                Box::pin(Self::fetch_payloads(
+                    self.watcher.client.clone(),
+                    self.watcher.network.clone(),
+                    added(changes),
+                    self.batch_size,
+                ))
+            });
+            let fetched = match pending.as_mut().poll(cx) {
+                Poll::Ready(Ok(fetched)) => fetched,
+                Poll::Ready(Err(error)) => {
+                    return Poll::Ready(Err(self.fail(error)))
+                }
+                Poll::Pending => return Poll::Pending,
+            };
+            self.poll_state.payloads = None;
+            self.poll_state.fetched = Some(fetched);
+        }
+        let page = match self.committed_stream.poll_page(cx, None) {
+            Poll::Ready(Ok(page)) => page,
+            Poll::Ready(Err(error)) => {
+                return Poll::Ready(Err(self.fail(error)))
+            }
+            Poll::Pending => return Poll::Pending,
+        };
+        let PollState {
+            changes, fetched, ..
+        } = std::mem::take(&mut self.poll_state);
+        self.polled = true;
+        Poll::Ready(Ok(self.handle(&changes.unwrap(), fetched.unwrap(), page)))
+    }
+
+    /// Ends a poll that failed after the snapshot,
+    /// whose changes are handled again on the next poll.
+    fn fail(&mut self, error: CoreApiError) -> CoreApiError {
+        let PollState { changes, .. } = std::mem::take(&mut self.poll_state);
+        self.unhandled = changes.unwrap_or_default();
+        self.polled = true;
+        error
+    }
+}
+
+impl MempoolMonitorBlocking {
+    /// Polls the mempool and the ledger once and returns what happened
+    /// since the last poll. After an error, the changes of the mempool
+    /// are handled again on the next poll.
+    pub fn next_events(
+        &mut self,
+    ) -> Result<Vec<MempoolMonitorEvent>, CoreApiError> {
+        self.polled = true;
+        let mut changes = self.watcher.next_changes()?;
+        changes.splice(0..0, std::mem::take(&mut self.unhandled));
+        let result = Self::fetch_payloads(
+            self.watcher.client.clone(),
+            self.watcher.network.clone(),
+            added(&changes),
+            self.batch_size,
+        )
+        .and_then(|payloads| {
+            let page = self.committed_stream.next_page()?;
+            Ok(self.handle(&changes, payloads, page))
+        });
+        if result.is_err() {
+            self.unhandled = changes;
+        }
+        result
+    }
+}
+
+/// The transactions that entered the mempool.
+fn added(changes: &[MempoolEvent]) -> Vec<MempoolTransactionHashes> {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            MempoolEvent::Added(hashes) => Some(hashes.clone()),
+            MempoolEvent::Removed(_) => None,
+        })
+        .collect()
+}
+
+/// The payload of a transaction the node did not return a payload for.
+fn missing_payload(
+    hashes: &MempoolTransactionHashes,
+) -> MempoolTransactionPayloads {
+    MempoolTransactionPayloads {
+        hash: hashes.payload_hash.clone(),
+        hash_bech32m: hashes.payload_hash_bech32m.clone(),
+        hex: None,
+        error: Some("Payload not returned by the node".to_string()),
+    }
+}
+
+impl Stream for MempoolMonitorAsync {
+    type Item = Result<MempoolMonitorEvent, CoreApiError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            let delay = this.polled.then_some(this.watcher.poll_interval);
+            match this.poll_events(cx, delay) {
+                Poll::Ready(Ok(events)) => this.events.extend(events),
+                Poll::Ready(Err(error)) => {
+                    return Poll::Ready(Some(Err(error)))
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Iterator for MempoolMonitorBlocking {
+    type Item = Result<MempoolMonitorEvent, CoreApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.polled {
+                std::thread::sleep(self.watcher.poll_interval);
+            }
+            match self.next_events() {
+                Ok(events) => self.events.extend(events),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
+
+#[duplicate_item(
+    monitor_type                 client_type              maybe_async_attr ;
+    [ MempoolMonitorAsync ]      [ CoreClientAsync ]      [ must_be_async ];
+    [ MempoolMonitorBlocking ]   [ CoreClientBlocking ]   [ must_be_sync ];
+)]
+impl client_type {
+    #[maybe_async_attr]
+    pub async fn monitor_mempool(
+        &self,
+        network: String,
+        from_state_version: u64,
+    ) -> Result<monitor_type, CoreApiError> {
+        monitor_type::new(self, network, from_state_version).await
+    }
+
+    #[maybe_async_attr]
+    pub async fn monitor_mempool_from_ledger_tip(
+        &self,
+        network: String,
+    ) -> Result<monitor_type, CoreApiError> {
+        monitor_type::from_ledger_tip(self, network).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::test_utils::{mempool_list, mempool_payloads};
+    use crate::core::stream::test_utils::{
+        transaction_page, user_transaction_page,
+    };
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use reqwest::StatusCode;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn describe(event: &MempoolMonitorEvent) -> String {
+        let hash = &event.hashes().payload_hash;
+        match event {
+            MempoolMonitorEvent::Added { .. } => format!("added {hash}"),
+            MempoolMonitorEvent::Removed { .. } => format!("removed {hash}"),
+            MempoolMonitorEvent::Committed { state_version, .. } => {
+                format!("committed {hash} at {state_version}")
+            }
+            MempoolMonitorEvent::Dropped { .. } => format!("dropped {hash}"),
+        }
+    }
+
+    #[test]
+    fn reports_added_committed_and_dropped_transactions() {
+        let mock = Rc::new(MockTransport::new());
+        // The ledger tip is read first.
+        mock.respond_ok("stream/transactions", &transaction_page(4, &[1]));
+        mock.respond_ok("mempool/list", &mempool_list(&["a", "b", "c"]));
+        mock.respond_ok("mempool/transaction", &mempool_payloads(&["a", "b"]));
+        mock.respond_ok("mempool/transaction", &mempool_payloads(&["c"]));
+        mock.respond_ok("stream/transactions", &transaction_page(4, &[]));
+        mock.respond_ok("mempool/list", &mempool_list(&["c"]));
+        mock.respond_ok(
+            "stream/transactions",
+            &user_transaction_page(5, &[(5, "a")]),
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut monitor = client
+            .monitor_mempool("mainnet".to_string(), 5)
+            .unwrap()
+            .batch_size(2)
+            .drop_after(Duration::ZERO);
+
+        let events = monitor.next_events().unwrap();
+        assert_eq!(
+            events.iter().map(describe).collect::<Vec<_>>(),
+            ["added a", "added b", "added c"]
+        );
+        assert_eq!(events[2].payload().hex.as_deref(), Some("4dc"));
+
+        let events = monitor.next_events().unwrap();
+        assert_eq!(
+            events.iter().map(describe).collect::<Vec<_>>(),
+            ["removed a", "removed b", "committed a at 5", "dropped b"]
+        );
+        assert_eq!(events[3].payload().hex.as_deref(), Some("4db"));
+    }
+
+    #[test]
+    fn rejects_a_start_far_behind_the_ledger_tip() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20_000, &[1]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+
+        assert!(matches!(
+            client.monitor_mempool("mainnet".to_string(), 1),
+            Err(CoreApiError::InvalidStream(
+                StreamConfigError::TooFarBehind {
+                    from_state_version: 1,
+                    ledger_tip: 20_000,
+                    ..
+                }
+            ))
+        ));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn starts_after_the_ledger_tip() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20_000, &[1]));
+        mock.respond_ok("mempool/list", &mempool_list(&[]));
+        mock.respond_ok(
+            "stream/transactions",
+            &user_transaction_page(20_001, &[(20_000, "a"), (20_001, "b")]),
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let mut monitor = client
+            .monitor_mempool_from_ledger_tip("mainnet".to_string())
+            .await
+            .unwrap();
+
+        assert!(monitor.next_events().await.unwrap().is_empty());
+        // The tip transaction is asked for and filtered out.
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[2].body).unwrap();
+        assert_eq!(body["from_state_version"], 20_000);
+        assert_eq!(monitor.committed.keys().collect::<Vec<_>>(), ["b"]);
+    }
+
+    #[tokio::test]
+    async fn handles_the_changes_again_after_an_error() {
+        use futures::StreamExt;
+
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(1, &[1]));
+        mock.respond_ok("mempool/list", &mempool_list(&["a"]));
+        mock.respond("mempool/transaction", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("mempool/list", &mempool_list(&["a"]));
+        mock.respond_ok("mempool/transaction", &mempool_payloads(&["a"]));
+        mock.respond_ok("stream/transactions", &transaction_page(1, &[]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock)
+            .build_async()
+            .unwrap();
+        let events: Vec<_> = client
+            .monitor_mempool("mainnet".to_string(), 1)
+            .await
+            .unwrap()
+            .poll_interval(Duration::from_millis(1))
+            .take(2)
+            .collect()
+            .await;
+
+        assert!(events[0].is_err());
+        assert_eq!(describe(events[1].as_ref().unwrap()), "added a");
+    }
+}
@@ -0,0 +1,325 @@
+//! # Mempool watcher
+//!
+//! Polls `mempool/list` of a node and compares every snapshot with the
+//! previous one. Transactions that appeared are reported as
+//! [`MempoolEvent::Added`], transactions that are gone, because they were
+//! committed or dropped, as [`MempoolEvent::Removed`]. The first snapshot
+//! reports everything in the mempool as added.
+
+use crate::core::error::CoreApiError;
+use crate::core::models::*;
+use crate::stream_config::error_backoff;
+use crate::transport::BoxFuture;
+use crate::{CoreClientAsync, CoreClientBlocking};
+use duplicate::duplicate_item;
+use futures_core::Stream;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+type SnapshotResult = Result<GetMempoolList200Response, CoreApiError>;
+
+#[derive(Debug, Clone)]
+pub enum MempoolEvent {
+    Added(MempoolTransactionHashes),
+    Removed(MempoolTransactionHashes),
+}
+
+impl MempoolEvent {
+    pub fn hashes(&self) -> &MempoolTransactionHashes {
+        match self {
+            MempoolEvent::Added(hashes) | MempoolEvent::Removed(hashes) => {
+                hashes
+            }
+        }
+    }
+}
+
+/// A snapshot request in flight.
+struct PendingSnapshot(BoxFuture<'static, SnapshotResult>);
+
+impl Debug for PendingSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PendingSnapshot").finish()
+    }
+}
+
+/// Reports the changes between successive snapshots of the mempool.
+/// Besides fetching a snapshot with `.next_changes()`, the async watcher
+/// implements `futures::Stream` and the blocking one `Iterator`, yielding
+/// one event at a time and waiting `poll_interval` between snapshots.
+/// Errors are yielded as items, after which the watcher keeps polling with
+/// an exponential backoff, see [`error_backoff`].
+#[duplicate_item(
+    watcher_type              client_type              pending_type ;
+    [ MempoolWatcherAsync ]   [ CoreClientAsync ]      [ Option<PendingSnapshot> ];
+    [ MempoolWatcherBlocking ] [ CoreClientBlocking ]  [ () ];
+)]
+#[derive(Debug)]
+pub struct watcher_type {
+    pub client: client_type,
+    pub network: String,
+    pub poll_interval: Duration,
+    /// The transactions of the last snapshot, by payload hash.
+    known: HashMap<String, MempoolTransactionHashes>,
+    events: VecDeque<MempoolEvent>,
+    polled: bool,
+    /// Failed polls in a row, when used as a `Stream` or `Iterator`.
+    consecutive_errors: u32,
+    // The blocking watcher fetches snapshots in place.
+    pending: pending_type,
+}
+
+#[duplicate_item(
+    watcher_type               client_type              new_pending ;
+    [ MempoolWatcherAsync ]    [ CoreClientAsync ]      [ None ];
+    [ MempoolWatcherBlocking ] [ CoreClientBlocking ]   [ () ];
+)]
+impl watcher_type {
+    pub fn new(client: &client_type, network: String) -> watcher_type {
+        watcher_type {
+            client: client.clone(),
+            network,
+            poll_interval: Duration::from_secs(1),
+            known: HashMap::new(),
+            events: VecDeque::new(),
+            polled: false,
+            consecutive_errors: 0,
+            pending: new_pending,
+        }
+    }
+
+    /// How long to wait between snapshots, when used as
+    /// a `Stream` or `Iterator`. Defaults to one second.
+    pub fn poll_interval(mut self, value: Duration) -> Self {
+        self.poll_interval = value;
+        self
+    }
+
+    /// The transactions in the last snapshot.
+    pub fn known(&self) -> impl Iterator<Item = &MempoolTransactionHashes> {
+        self.known.values()
+    }
+
+    /// Replaces the last snapshot and returns what changed: the removed
+    /// transactions by payload hash, then the added ones in node order.
+    fn diff(
+        &mut self,
+        snapshot: GetMempoolList200Response,
+    ) -> Vec<MempoolEvent> {
+        let mut previous = std::mem::take(&mut self.known);
+        let mut added = vec![];
+        for hashes in snapshot.contents {
+            if previous.remove(&hashes.payload_hash).is_none() {
+                added.push(MempoolEvent::Added(hashes.clone()));
+            }
+            self.known.insert(hashes.payload_hash.clone(), hashes);
+        }
+        let mut removed: Vec<_> = previous.into_values().collect();
+        removed.sort_by(|a, b| a.payload_hash.cmp(&b.payload_hash));
+        removed
+            .into_iter()
+            .map(MempoolEvent::Removed)
+            .chain(added)
+            .collect()
+    }
+}
+
+impl MempoolWatcherAsync {
+    /// Fetches a snapshot and returns what changed since the last one.
+    pub async fn next_changes(
+        &mut self,
+    ) -> Result<Vec<MempoolEvent>, CoreApiError> {
+        std::future::poll_fn(|cx| self.poll_changes(cx, None)).await
+    }
+
+    /// Polls for the changes of the next snapshot,
+    /// which is requested after `delay`.
+    pub(crate) fn poll_changes(
+        &mut self,
+        cx: &mut Context<'_>,
+        delay: Option<Duration>,
+    ) -> Poll<Result<Vec<MempoolEvent>, CoreApiError>> {
+        let pending = self.pending.get_or_insert_with(|| {
+            let client = self.client.clone();
+            let network = self.network.clone();
+            PendingSnapshot(Box::pin(async move {
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                client.mempool_list(network).await
+            }))
+        });
+        let result = match pending.0.as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.pending = None;
+        self.polled = true;
+        Poll::Ready(result.map(|snapshot| self.diff(snapshot)))
+    }
+}
+
+impl MempoolWatcherBlocking {
+    /// Fetches a snapshot and returns what changed since the last one.
+    pub fn next_changes(&mut self) -> Result<Vec<MempoolEvent>, CoreApiError> {
+        let snapshot = self.client.mempool_list(self.network.clone())?;
+        self.polled = true;
+        Ok(self.diff(snapshot))
+    }
+}
+
+impl Stream for MempoolWatcherAsync {
+    type Item = Result<MempoolEvent, CoreApiError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            let delay =
+                error_backoff(this.poll_interval, this.consecutive_errors)
+                    .or(this.polled.then_some(this.poll_interval));
+            match this.poll_changes(cx, delay) {
+                Poll::Ready(Ok(events)) => {
+                    this.consecutive_errors = 0;
+                    this.events.extend(events)
+                }
+                Poll::Ready(Err(error)) => {
+                    this.consecutive_errors += 1;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Iterator for MempoolWatcherBlocking {
+    type Item = Result<MempoolEvent, CoreApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            let delay =
+                error_backoff(self.poll_interval, self.consecutive_errors)
+                    .or(self.polled.then_some(self.poll_interval));
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            match self.next_changes() {
+                Ok(events) => {
+                    self.consecutive_errors = 0;
+                    self.events.extend(events)
+                }
+                Err(error) => {
+                    self.consecutive_errors += 1;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[duplicate_item(
+    watcher_type               client_type ;
+    [ MempoolWatcherAsync ]    [ CoreClientAsync ];
+    [ MempoolWatcherBlocking ] [ CoreClientBlocking ];
+)]
+impl client_type {
+    pub fn watch_mempool(&self, network: String) -> watcher_type {
+        watcher_type::new(self, network)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::mempool::test_utils::mempool_list;
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use reqwest::StatusCode;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn describe(event: &MempoolEvent) -> String {
+        match event {
+            MempoolEvent::Added(hashes) => format!("+{}", hashes.payload_hash),
+            MempoolEvent::Removed(hashes) => {
+                format!("-{}", hashes.payload_hash)
+            }
+        }
+    }
+
+    #[test]
+    fn diffs_successive_snapshots() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("mempool/list", &mempool_list(&["a", "b"]));
+        mock.respond_ok("mempool/list", &mempool_list(&["b", "c"]));
+        mock.respond_ok("mempool/list", &mempool_list(&["b", "c"]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .build_blocking()
+            .unwrap();
+        let mut watcher = client.watch_mempool("mainnet".to_string());
+
+        let changes = |events: Vec<MempoolEvent>| {
+            events.iter().map(describe).collect::<Vec<_>>()
+        };
+        assert_eq!(changes(watcher.next_changes().unwrap()), ["+a", "+b"]);
+        assert_eq!(changes(watcher.next_changes().unwrap()), ["-a", "+c"]);
+        assert!(watcher.next_changes().unwrap().is_empty());
+        assert_eq!(watcher.known().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn stream_yields_events_and_keeps_polling_after_errors() {
+        use futures::StreamExt;
+
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("mempool/list", &mempool_list(&["a"]));
+        mock.respond("mempool/list", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("mempool/list", &mempool_list(&[]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock)
+            .build_async()
+            .unwrap();
+        let events: Vec<_> = client
+            .watch_mempool("mainnet".to_string())
+            .poll_interval(Duration::from_millis(1))
+            .take(3)
+            .collect()
+            .await;
+
+        assert_eq!(describe(events[0].as_ref().unwrap()), "+a");
+        assert!(events[1].is_err());
+        assert_eq!(describe(events[2].as_ref().unwrap()), "-a");
+    }
+
+    #[test]
+    fn iterator_backs_off_after_errors() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond("mempool/list", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("mempool/list", &mempool_list(&["a"]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .build_blocking()
+            .unwrap();
+        let mut watcher = client
+            .watch_mempool("mainnet".to_string())
+            .poll_interval(Duration::from_millis(50));
+
+        assert!(watcher.next().unwrap().is_err());
+        let started = std::time::Instant::now();
+        assert_eq!(describe(&watcher.next().unwrap().unwrap()), "+a");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -9,13 +9,13 @@ pub struct GetMempoolTransactionRequest {
     pub payload_hashes: Vec<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, Serialize)]
 pub struct GetMempoolTransaction200Response {
     pub count: u32,
     pub payloads: Vec<MempoolTransactionPayloads>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, Serialize)]
 pub struct MempoolTransactionPayloads {
     pub hash: String,
     pub hash_bech32m: String,
@@ -28,12 +28,12 @@ pub struct GetMempoolListRequest {
     pub network: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, Serialize)]
 pub struct GetMempoolList200Response {
     pub contents: Vec<MempoolTransactionHashes>,
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default, Serialize)]
 pub struct MempoolTransactionHashes {
     pub intent_hash: String,
     pub intent_hash_bech32m: String,
@@ -48,30 +48,20 @@ pub enum PublicKeyType {
     EddsaEd25519,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct PublicKey {
     pub key_type: PublicKeyType,
     // The hex-encoded compressed EdDSA Ed25519 public key (32 bytes)
     pub key_hex: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PreviewTransactionFlags {
     pub use_free_credit: bool,
     pub assume_all_signature_proofs: bool,
     pub skip_epoch_check: bool,
 }
 
-impl Default for PreviewTransactionFlags {
-    fn default() -> Self {
-        PreviewTransactionFlags {
-            use_free_credit: false,
-            assume_all_signature_proofs: false,
-            skip_epoch_check: false,
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct TransactionPreviewRequestBody {
     pub network: String,
@@ -331,14 +321,86 @@ pub struct CostingParameters {
     pub tip_percentage: u8,
 }
 
-// Cutting some corners here until I ever need this.
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct StateUpdates {
-    pub deleted_partitions: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub created_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub updated_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub deleted_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub new_global_entities: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
+    pub deleted_partitions: Vec<PartitionId>,
+    pub created_substates: Vec<CreatedSubstate>,
+    pub updated_substates: Vec<UpdatedSubstate>,
+    pub deleted_substates: Vec<DeletedSubstate>,
+    pub new_global_entities: Vec<EntityReference>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionId {
+    pub entity_type: EntityType,
+    pub entity_address: String,
+    pub partition_number: u8,
+    pub partition_kind: PartitionKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PartitionKind {
+    Field,
+    KeyValue,
+    Index,
+    SortedIndex,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubstateId {
+    pub entity_type: EntityType,
+    pub entity_address: String,
+    pub partition_number: u8,
+    pub partition_kind: PartitionKind,
+    /// E.g. `FungibleVaultFieldBalance`. Kept as a string, new substate
+    /// types are added with every protocol update.
+    pub substate_type: String,
+    pub substate_key: SubstateKey,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "key_type")]
+pub enum SubstateKey {
+    Field {
+        id: u8,
+    },
+    Map {
+        key_hex: String,
+    },
+    Sorted {
+        sort_prefix_hex: String,
+        key_hex: String,
+    },
+}
+
+// The substate data and system structure are unions of many substate
+// types, they are kept as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubstateValue {
+    pub substate_hex: Option<String>,
+    pub substate_data: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedSubstate {
+    pub substate_id: SubstateId,
+    pub value: SubstateValue,
+    pub system_structure: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdatedSubstate {
+    pub substate_id: SubstateId,
+    pub new_value: SubstateValue,
+    pub previous_value: Option<SubstateValue>,
+    pub system_structure: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeletedSubstate {
+    pub substate_id: SubstateId,
+    pub previous_value: Option<SubstateValue>,
+    pub system_structure: serde_json::Value,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -384,12 +446,16 @@ pub struct SubstateFormatOptions {
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct TransactionStream200ResponseBody {
-    // pub previous_state_identifier: Option<CommittedStateIdentifier>,
+    /// The state before the first transaction, missing
+    /// when streaming from the start of the ledger.
+    pub previous_state_identifiers: Option<CommittedStateIdentifier>,
     pub from_state_version: Option<u64>,
     pub count: u32,
     pub max_ledger_state_version: u64,
     pub transactions: Vec<CommittedTransaction>,
-    // pub proofs: Option<Vec<LedgerProof>>,
+    /// Only returned with `include_proofs`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proofs: Vec<LedgerProof>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -400,9 +466,78 @@ pub struct CommittedTransaction {
     pub proposer_timestamp_ms: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
 pub struct CommittedStateIdentifier {
     pub state_version: u64,
+    pub state_tree_hash: String,
+    pub transaction_tree_hash: String,
+    pub receipt_tree_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LedgerProof {
+    pub ledger_header: LedgerHeader,
+    pub origin: LedgerProofOrigin,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LedgerHeader {
+    pub epoch: u64,
+    pub round: u64,
+    pub state_version: u64,
+    pub hashes: LedgerHashes,
+    pub consensus_parent_round_timestamp_ms: i64,
+    pub proposer_timestamp_ms: i64,
+    /// Set on the last proof of an epoch.
+    pub next_epoch: Option<NextEpoch>,
+    /// Set when the ledger moves to a new protocol version.
+    pub next_protocol_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct LedgerHashes {
+    pub state_tree_hash: String,
+    pub transaction_tree_hash: String,
+    pub receipt_tree_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum LedgerProofOrigin {
+    Genesis(GenesisLedgerProofOrigin),
+    Consensus(ConsensusLedgerProofOrigin),
+    ProtocolUpdate(ProtocolUpdateLedgerProofOrigin),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GenesisLedgerProofOrigin {
+    pub genesis_opaque_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsensusLedgerProofOrigin {
+    pub opaque_hash: String,
+    pub timestamped_signatures: Vec<TimestampedValidatorSignature>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProtocolUpdateLedgerProofOrigin {
+    pub protocol_version_name: String,
+    pub batch_idx: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimestampedValidatorSignature {
+    pub validator_key: EcdsaSecp256k1PublicKey,
+    pub validator_address: String,
+    pub timestamp_ms: i64,
+    pub signature: EcdsaSecp256k1Signature,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct EcdsaSecp256k1Signature {
+    pub key_type: PublicKeyType,
+    pub signature_hex: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -412,7 +547,7 @@ pub struct LedgerTransaction {}
 #[serde(tag = "type")]
 pub enum LedgerTransactionType {
     Genesis(GenesisLedgerTransaction),
-    User(UserLedgerTransaction),
+    User(Box<UserLedgerTransaction>),
     RoundUpdate(RoundUpdateLedgerTransaction),
 }
 
@@ -466,14 +601,14 @@ pub struct TransactionHeader {
     pub start_epoch_inclusive: u64,
     pub end_epoch_exclusive: u64,
     pub nonce: u64,
-    // pub notary_public_key: PublicKey,
+    pub notary_public_key: PublicKey,
     pub notary_is_signatory: bool,
     pub tip_percentage: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct RoundUpdateLedgerTransaction {
-    // pub payload_hex: Option<String>,
+    pub payload_hex: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -481,14 +616,15 @@ pub struct Transactionsubmit200ResponseBody {
     pub duplicate: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScryptoCallPreviewRequestBody {
     pub network: String,
     pub target: TargetIdentifier,
+    /// Every argument as hex-encoded Scrypto SBOR.
     pub arguments: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "type")]
 pub enum TargetIdentifier {
     Function {
@@ -502,17 +638,152 @@ pub enum TargetIdentifier {
     },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ScryptoCallPreview200ResponseBody {
     pub at_ledger_state: LedgerStateSummary,
-    pub status: String,
+    pub status: Status,
     pub output: Option<SborData>,
     pub error_message: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LedgerStateSummary {
-    // NOT IMPLEMENTED
-    // pub state_version: u64,
-    // pub header_summary: LedgerHeaderSummary,
+    pub state_version: u64,
+    pub header_summary: LedgerHeaderSummary,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LedgerHeaderSummary {
+    pub epoch_round: EpochRound,
+    pub ledger_hashes: LedgerHashes,
+    pub proposer_timestamp: InstantMs,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct EpochRound {
+    pub epoch: u64,
+    pub round: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstantMs {
+    pub unix_timestamp_ms: i64,
+    pub date_time: chrono::DateTime<chrono::Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::round_trip;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!("../../tests/fixtures/core/", $name))
+        };
+    }
+
+    #[test]
+    fn mempool_list() {
+        let res: GetMempoolList200Response =
+            round_trip(fixture!("mempool_list.json"));
+        assert_eq!(res.contents.len(), 2);
+        assert!(res.contents[0].intent_hash_bech32m.starts_with("txid_rdx"));
+    }
+
+    #[test]
+    fn mempool_transaction() {
+        let res: GetMempoolTransaction200Response =
+            round_trip(fixture!("mempool_transaction.json"));
+        assert_eq!(res.count, 2);
+        assert!(res.payloads[0].hex.is_some());
+        assert!(res.payloads[1].error.is_some());
+    }
+
+    #[test]
+    fn transaction_preview() {
+        let res: TransactionPreview200ResponseBody =
+            round_trip(fixture!("transaction_preview.json"));
+        assert!(matches!(res.receipt.status, Status::Succeeded));
+        let events = res.receipt.events.as_ref().unwrap();
+        assert_eq!(events[0].r#type.name, "PayFeeEvent");
+        let updates = &res.receipt.state_updates;
+        assert_eq!(
+            updates.created_substates[0].substate_id.partition_kind,
+            PartitionKind::Field
+        );
+        assert_eq!(
+            updates.created_substates[0].substate_id.substate_key,
+            SubstateKey::Field { id: 0 }
+        );
+        assert_eq!(
+            updates.deleted_partitions[0].entity_type,
+            EntityType::InternalKeyValueStore
+        );
+        assert_eq!(
+            res.instruction_resource_changes[0].resource_changes[0].amount,
+            Decimal::from(-100)
+        );
+    }
+
+    #[test]
+    fn transaction_submit() {
+        let res: Transactionsubmit200ResponseBody =
+            round_trip(fixture!("transaction_submit.json"));
+        assert!(!res.duplicate);
+    }
+
+    #[test]
+    fn stream_transactions() {
+        let res: TransactionStream200ResponseBody =
+            round_trip(fixture!("stream_transactions.json"));
+        assert_eq!(res.count, 2);
+        assert_eq!(res.max_ledger_state_version, 100246389);
+        let user = &res.transactions[0];
+        assert_eq!(user.resultant_state_identifiers.state_version, 100246366);
+        match &user.ledger_transaction {
+            LedgerTransactionType::User(tx) => assert_eq!(
+                tx.notarized_transaction.signed_intent.intent.hash_bech32m,
+                "txid_rdx1ysz4dgvz0kqr8q9ugeu2sjuwwu06n2qeym4tpkj0mpscc6xp2ups8zh8k9"
+            ),
+            other => panic!("expected a user transaction, got {:?}", other),
+        }
+        assert!(matches!(
+            res.transactions[1].ledger_transaction,
+            LedgerTransactionType::RoundUpdate(_)
+        ));
+        let previous = res.previous_state_identifiers.unwrap();
+        assert_eq!(previous.state_version, 100246365);
+        assert_eq!(previous.receipt_tree_hash.len(), 64);
+        assert!(res.proofs.is_empty());
+    }
+
+    #[test]
+    fn stream_transactions_with_proofs() {
+        let res: TransactionStream200ResponseBody =
+            round_trip(fixture!("stream_transactions_with_proofs.json"));
+        let last = &res.transactions[1].resultant_state_identifiers;
+        let header = &res.proofs[0].ledger_header;
+        assert_eq!(header.state_version, last.state_version);
+        assert_eq!(header.hashes.state_tree_hash, last.state_tree_hash);
+        assert!(header.next_epoch.is_none());
+        match &res.proofs[0].origin {
+            LedgerProofOrigin::Consensus(origin) => {
+                assert_eq!(origin.timestamped_signatures.len(), 1)
+            }
+            other => panic!("expected a consensus proof, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scrypto_call_preview() {
+        let res: ScryptoCallPreview200ResponseBody =
+            round_trip(fixture!("scrypto_call_preview.json"));
+        assert!(matches!(res.status, Status::Succeeded));
+        assert_eq!(res.at_ledger_state.state_version, 100246389);
+        let header = &res.at_ledger_state.header_summary;
+        assert_eq!(header.epoch_round.epoch, 98752);
+        assert_eq!(header.proposer_timestamp.unix_timestamp_ms, 1718185291020);
+        assert_eq!(header.ledger_hashes.state_tree_hash.len(), 64);
+        assert!(res.output.unwrap().hex.is_some());
+    }
 }
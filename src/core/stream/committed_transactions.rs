@@ -18,9 +18,9 @@ impl client_type {
     pub async fn committed_transactions(
         &self,
         request: GetCommittedTransactionsRequest,
-    ) -> Result<GetCommittedTransactionsRequest, CoreApiError> {
+    ) -> Result<TransactionStream200ResponseBody, CoreApiError> {
         let (text, status) = self.post("stream/transactions", request).await?;
-        match_response(text, status)
+        self.observe("stream/transactions", match_response(text, status))
     }
 }
 
@@ -99,9 +99,47 @@ impl builder_type<GetCommittedTransactionsRequest> {
     #[maybe_async_attr]
     pub async fn fetch(
         &self,
-    ) -> Result<GetCommittedTransactionsRequest, CoreApiError> {
+    ) -> Result<TransactionStream200ResponseBody, CoreApiError> {
         self.client
             .committed_transactions(self.request.clone())
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::core::models::LedgerProofOrigin;
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use std::rc::Rc;
+
+    #[test]
+    fn fetch_returns_the_transactions() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok(
+            "stream/transactions",
+            include_str!(
+                "../../../tests/fixtures/core/stream_transactions_with_proofs.json"
+            ),
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let response = client
+            .committed_transactions_builder("mainnet".to_string(), 100246366, 2)
+            .include_proofs()
+            .fetch()
+            .unwrap();
+
+        assert_eq!(response.transactions.len(), 2);
+        assert!(matches!(
+            response.proofs[0].origin,
+            LedgerProofOrigin::Consensus(_)
+        ));
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[0].body).unwrap();
+        assert_eq!(body["from_state_version"], 100246366);
+        assert_eq!(body["include_proofs"], true);
+    }
+}
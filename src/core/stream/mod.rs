@@ -1 +1,132 @@
 pub mod committed_transactions;
+pub mod proofs;
+pub mod stream_client;
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use serde_json::json;
+
+    /// A Core `stream/transactions` response with one round update
+    /// transaction for each of the given state versions.
+    pub fn transaction_page(
+        max_ledger_state_version: u64,
+        versions: &[u64],
+    ) -> String {
+        let round_update = json!({
+            "type": "RoundUpdate",
+            "payload_hex": "5c22010121022105a0"
+        });
+        page(
+            max_ledger_state_version,
+            versions
+                .iter()
+                .map(|state_version| (*state_version, round_update.clone()))
+                .collect(),
+        )
+    }
+
+    /// A Core `stream/transactions` response with a user transaction
+    /// for each of the given state versions and payload hashes.
+    pub fn user_transaction_page(
+        max_ledger_state_version: u64,
+        transactions: &[(u64, &str)],
+    ) -> String {
+        page(
+            max_ledger_state_version,
+            transactions
+                .iter()
+                .map(|(state_version, payload_hash)| {
+                    let user = json!({
+                        "type": "User",
+                        "notarized_transaction": {
+                            "hash": payload_hash,
+                            "hash_bech32m": format!("notarizedtransaction_{payload_hash}"),
+                            "signed_intent": {
+                                "hash": format!("signed_{payload_hash}"),
+                                "hash_bech32m": format!("signedintent_{payload_hash}"),
+                                "intent": {
+                                    "hash": format!("intent_{payload_hash}"),
+                                    "hash_bech32m": format!("txid_{payload_hash}"),
+                                    "header": {
+                                        "network_id": 1,
+                                        "start_epoch_inclusive": 1,
+                                        "end_epoch_exclusive": 2,
+                                        "nonce": 0,
+                                        "notary_public_key": {
+                                            "key_type": "EddsaEd25519",
+                                            "key_hex": "00"
+                                        },
+                                        "notary_is_signatory": true,
+                                        "tip_percentage": 0
+                                    }
+                                }
+                            }
+                        }
+                    });
+                    (*state_version, user)
+                })
+                .collect(),
+        )
+    }
+
+    fn page(
+        max_ledger_state_version: u64,
+        transactions: Vec<(u64, serde_json::Value)>,
+    ) -> String {
+        let versions: Vec<_> = transactions
+            .iter()
+            .map(|(state_version, _)| *state_version)
+            .collect();
+        let transactions: Vec<_> = transactions
+            .into_iter()
+            .map(|(state_version, ledger_transaction)| {
+                json!({
+                    "resultant_state_identifiers": {
+                        "state_version": state_version,
+                        "state_tree_hash": format!("{state_version:064x}"),
+                        "transaction_tree_hash": format!("{state_version:064x}"),
+                        "receipt_tree_hash": format!("{state_version:064x}")
+                    },
+                    "ledger_transaction": ledger_transaction,
+                    "receipt": {
+                        "status": "Succeeded",
+                        "fee_summary": {
+                            "execution_cost_units_consumed": 0,
+                            "finalization_cost_units_consumed": 0,
+                            "xrd_total_execution_cost": "0",
+                            "xrd_total_finalization_cost": "0",
+                            "xrd_total_royalty_cost": "0",
+                            "xrd_total_storage_cost": "0",
+                            "xrd_total_tipping_cost": "0"
+                        },
+                        "costing_parameters": {
+                            "execution_cost_unit_price": "0.000000005",
+                            "execution_cost_unit_limit": 100000000,
+                            "execution_cost_unit_loan": 0,
+                            "finalization_cost_unit_price": "0.000000005",
+                            "finalization_cost_unit_limit": 50000000,
+                            "xrd_usd_price": "16.666666666666666666",
+                            "xrd_storage_price": "0.00009536743164062",
+                            "tip_percentage": 0
+                        },
+                        "state_updates": {
+                            "deleted_partitions": [],
+                            "created_substates": [],
+                            "updated_substates": [],
+                            "deleted_substates": [],
+                            "new_global_entities": []
+                        }
+                    },
+                    "proposer_timestamp_ms": 1718185285811u64
+                })
+            })
+            .collect();
+        json!({
+            "from_state_version": versions.first(),
+            "count": versions.len(),
+            "max_ledger_state_version": max_ledger_state_version,
+            "transactions": transactions
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,108 @@
+//! # Proofs
+//!
+//! With `include_proofs`, a page of the Core `stream/transactions` endpoint
+//! carries the ledger proofs of its transactions. Every proof ends at one
+//! of the transactions on the page, and its ledger header must hold the
+//! same state version and hashes as the `resultant_state_identifiers` of
+//! that transaction. The signatures of the proofs are not verified.
+
+use crate::core::models::*;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofMismatch {
+    /// No transaction on the page ends at the state version of a proof.
+    Uncovered { state_version: u64 },
+    /// The hashes of a proof differ from those of the transaction it ends
+    /// at.
+    Hashes { state_version: u64 },
+}
+
+impl Display for ProofMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ProofMismatch::Uncovered { state_version } => write!(
+                f,
+                "the proof at state version {} covers no transaction \
+                 of the page",
+                state_version
+            ),
+            ProofMismatch::Hashes { state_version } => write!(
+                f,
+                "the proof at state version {} does not match the hashes \
+                 of its transaction",
+                state_version
+            ),
+        }
+    }
+}
+
+/// Checks every proof of a page against the last transaction it covers.
+pub fn verify(
+    response: &TransactionStream200ResponseBody,
+) -> Result<(), ProofMismatch> {
+    for proof in &response.proofs {
+        let header = &proof.ledger_header;
+        let identifiers = response
+            .transactions
+            .iter()
+            .map(|transaction| &transaction.resultant_state_identifiers)
+            .find(|identifiers| {
+                identifiers.state_version == header.state_version
+            })
+            .ok_or(ProofMismatch::Uncovered {
+                state_version: header.state_version,
+            })?;
+        if identifiers.state_tree_hash != header.hashes.state_tree_hash
+            || identifiers.transaction_tree_hash
+                != header.hashes.transaction_tree_hash
+            || identifiers.receipt_tree_hash != header.hashes.receipt_tree_hash
+        {
+            return Err(ProofMismatch::Hashes {
+                state_version: header.state_version,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page() -> TransactionStream200ResponseBody {
+        serde_json::from_str(include_str!(
+            "../../../tests/fixtures/core/stream_transactions_with_proofs.json"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn accepts_matching_proofs() {
+        assert_eq!(verify(&page()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_proofs_with_other_hashes() {
+        let mut page = page();
+        page.proofs[0].ledger_header.hashes.receipt_tree_hash = "00".repeat(32);
+        assert_eq!(
+            verify(&page),
+            Err(ProofMismatch::Hashes {
+                state_version: 100246367
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_proofs_past_the_page() {
+        let mut page = page();
+        page.transactions.pop();
+        assert_eq!(
+            verify(&page),
+            Err(ProofMismatch::Uncovered {
+                state_version: 100246367
+            })
+        );
+    }
+}
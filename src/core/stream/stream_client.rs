@@ -0,0 +1,424 @@
+use crate::core::builder::{RequestBuilderAsync, RequestBuilderBlocking};
+use crate::core::error::CoreApiError;
+use crate::core::models::*;
+use crate::core::stream::proofs::{self, ProofMismatch};
+pub use crate::stream_config::StreamConfigError;
+use crate::stream_config::{error_backoff, validate};
+use crate::transport::BoxFuture;
+use crate::{CoreClientAsync, CoreClientBlocking};
+use duplicate::duplicate_item;
+use futures_core::Stream;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The largest page the Core API returns.
+pub const MAX_LIMIT_PER_PAGE: u32 = 10_000;
+
+type PageResult = Result<TransactionStream200ResponseBody, CoreApiError>;
+
+/// A page request in flight.
+struct PendingPage(BoxFuture<'static, PageResult>);
+
+impl Debug for PendingPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PendingPage").finish()
+    }
+}
+
+/// A managed stream over the Core API `stream/transactions` endpoint of a
+/// node, the counterpart of the Gateway
+/// [`TransactionStreamAsync`](crate::gateway::stream::stream_client::TransactionStreamAsync).
+/// It follows the ledger from a state version onwards, tracking its position
+/// by the `resultant_state_identifiers` of the transactions it received, and
+/// returns empty pages once it caught up with the node. With
+/// `include_proofs`, every page is checked against its proofs, see
+/// [`proofs`](crate::core::stream::proofs).
+///
+/// The async stream also implements `futures::Stream` and the blocking one
+/// `Iterator`, yielding one transaction at a time and waiting
+/// `idle_poll_interval` before fetching again when caught up. Errors are
+/// yielded as items, after which the same page is fetched again with an
+/// exponential backoff, see [`error_backoff`].
+#[duplicate_item(
+    stream_type                         builder_type                pending_type ;
+    [ CoreTransactionStreamAsync ]      [ RequestBuilderAsync ]     [ Option<PendingPage> ];
+    [ CoreTransactionStreamBlocking ]   [ RequestBuilderBlocking ]  [ () ];
+)]
+#[derive(Debug)]
+pub struct stream_type {
+    pub builder: builder_type<GetCommittedTransactionsRequest>,
+    pub last_seen_state_version: u64,
+    pub idle_poll_interval: Duration,
+    buffer: VecDeque<CommittedTransaction>,
+    caught_up: bool,
+    /// Failed polls in a row, when used as a `Stream` or `Iterator`.
+    consecutive_errors: u32,
+    // The blocking stream fetches pages in place.
+    pending: pending_type,
+}
+
+#[duplicate_item(
+    stream_type                         client_type              new_pending ;
+    [ CoreTransactionStreamAsync ]      [ CoreClientAsync ]      [ None ];
+    [ CoreTransactionStreamBlocking ]   [ CoreClientBlocking ]   [ () ];
+)]
+impl stream_type {
+    /// Starts at `from_state_version`, which must be at least 1, with
+    /// pages of up to `limit_per_page` transactions, which must be
+    /// between 1 and [`MAX_LIMIT_PER_PAGE`].
+    pub fn new(
+        client: &client_type,
+        network: String,
+        from_state_version: u64,
+        limit_per_page: u32,
+    ) -> Result<stream_type, StreamConfigError> {
+        validate(from_state_version, limit_per_page, MAX_LIMIT_PER_PAGE)?;
+        Ok(stream_type {
+            builder: client.committed_transactions_builder(
+                network,
+                from_state_version,
+                limit_per_page,
+            ),
+            last_seen_state_version: from_state_version - 1,
+            idle_poll_interval: Duration::from_secs(1),
+            buffer: VecDeque::new(),
+            caught_up: false,
+            consecutive_errors: 0,
+            pending: new_pending,
+        })
+    }
+
+    /// Includes the ledger proofs in every page. A page with a proof that
+    /// does not match its transactions is returned as a
+    /// [`CoreApiError::ProofMismatch`] and fetched again.
+    pub fn include_proofs(mut self) -> Self {
+        self.builder.include_proofs();
+        self
+    }
+
+    /// How long to wait before fetching again when caught up with
+    /// the node, when used as a `Stream` or `Iterator`.
+    /// Defaults to one second.
+    pub fn idle_poll_interval(mut self, value: Duration) -> Self {
+        self.idle_poll_interval = value;
+        self
+    }
+
+    /// Checks the proofs of a page, then drops the transactions that were
+    /// already seen and moves the stream past the remaining ones.
+    fn advance(
+        &mut self,
+        mut response: TransactionStream200ResponseBody,
+    ) -> Result<TransactionStream200ResponseBody, ProofMismatch> {
+        proofs::verify(&response)?;
+        response.transactions.retain(|transaction| {
+            transaction.resultant_state_identifiers.state_version
+                > self.last_seen_state_version
+        });
+        if let Some(transaction) = response.transactions.last() {
+            let state_version =
+                transaction.resultant_state_identifiers.state_version;
+            self.builder.request.from_state_version = state_version + 1;
+            self.last_seen_state_version = state_version;
+        }
+        self.caught_up = response.transactions.is_empty();
+
+        Ok(response)
+    }
+}
+
+impl CoreTransactionStreamAsync {
+    pub async fn next_page(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, CoreApiError> {
+        std::future::poll_fn(|cx| self.poll_page(cx, None)).await
+    }
+
+    /// Polls for the next page, which is requested after `delay`.
+    pub(crate) fn poll_page(
+        &mut self,
+        cx: &mut Context<'_>,
+        delay: Option<Duration>,
+    ) -> Poll<PageResult> {
+        let pending = self.pending.get_or_insert_with(|| {
+            let builder = self.builder.clone();
+            PendingPage(Box::pin(async move {
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+                builder.fetch().await
+            }))
+        });
+        let result = match pending.0.as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+        self.pending = None;
+        Poll::Ready(result.and_then(|response| {
+            self.advance(response).map_err(CoreApiError::ProofMismatch)
+        }))
+    }
+}
+
+impl CoreTransactionStreamBlocking {
+    pub fn next_page(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, CoreApiError> {
+        let response = self.builder.fetch()?;
+        self.advance(response).map_err(CoreApiError::ProofMismatch)
+    }
+}
+
+impl Stream for CoreTransactionStreamAsync {
+    type Item = Result<CommittedTransaction, CoreApiError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(transaction) = this.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(transaction)));
+            }
+            let delay =
+                error_backoff(this.idle_poll_interval, this.consecutive_errors)
+                    .or(this.caught_up.then_some(this.idle_poll_interval));
+            match this.poll_page(cx, delay) {
+                Poll::Ready(Ok(page)) => {
+                    this.consecutive_errors = 0;
+                    this.buffer.extend(page.transactions)
+                }
+                Poll::Ready(Err(error)) => {
+                    this.consecutive_errors += 1;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Iterator for CoreTransactionStreamBlocking {
+    type Item = Result<CommittedTransaction, CoreApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(transaction) = self.buffer.pop_front() {
+                return Some(Ok(transaction));
+            }
+            let delay =
+                error_backoff(self.idle_poll_interval, self.consecutive_errors)
+                    .or(self.caught_up.then_some(self.idle_poll_interval));
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            match self.next_page() {
+                Ok(page) => {
+                    self.consecutive_errors = 0;
+                    self.buffer.extend(page.transactions)
+                }
+                Err(error) => {
+                    self.consecutive_errors += 1;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+#[duplicate_item(
+    stream_type                         client_type ;
+    [ CoreTransactionStreamAsync ]      [ CoreClientAsync ];
+    [ CoreTransactionStreamBlocking ]   [ CoreClientBlocking ];
+)]
+impl client_type {
+    pub fn new_transaction_stream(
+        &self,
+        network: String,
+        from_state_version: u64,
+        limit_per_page: u32,
+    ) -> Result<stream_type, StreamConfigError> {
+        stream_type::new(self, network, from_state_version, limit_per_page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stream::test_utils::transaction_page;
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use reqwest::StatusCode;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn versions(page: &TransactionStream200ResponseBody) -> Vec<u64> {
+        page.transactions
+            .iter()
+            .map(|transaction| {
+                transaction.resultant_state_identifiers.state_version
+            })
+            .collect()
+    }
+
+    #[test]
+    fn next_page_advances_and_handles_empty_pages() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[6, 7]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream("mainnet".to_string(), 5, 2)
+            .unwrap()
+            .include_proofs();
+
+        assert_eq!(versions(&stream.next_page().unwrap()), vec![5, 6]);
+        assert_eq!(versions(&stream.next_page().unwrap()), vec![7]);
+        assert!(versions(&stream.next_page().unwrap()).is_empty());
+        assert_eq!(stream.last_seen_state_version, 7);
+
+        let from_state_versions: Vec<_> = mock
+            .requests()
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_str(&request.body).unwrap();
+                assert_eq!(body["include_proofs"], true);
+                body["from_state_version"].as_u64().unwrap()
+            })
+            .collect();
+        assert_eq!(from_state_versions, vec![5, 7, 8]);
+    }
+
+    #[tokio::test]
+    async fn stream_yields_transactions_and_polls_when_caught_up() {
+        use futures::StreamExt;
+
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[1, 2]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[]));
+        mock.respond("stream/transactions", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[3]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let stream = client
+            .new_transaction_stream("mainnet".to_string(), 1, 2)
+            .unwrap()
+            .idle_poll_interval(Duration::from_millis(1));
+
+        let items: Vec<_> = stream.take(4).collect().await;
+        let versions: Vec<_> = items
+            .iter()
+            .filter_map(|item| item.as_ref().ok())
+            .map(|transaction| {
+                transaction.resultant_state_identifiers.state_version
+            })
+            .collect();
+        assert_eq!(versions, vec![1, 2, 3]);
+        assert!(items[2].is_err());
+        assert_eq!(mock.requests().len(), 4);
+    }
+
+    /// A page with a proof at `state_version` with the given hash.
+    fn page_with_proof(
+        versions: &[u64],
+        state_version: u64,
+        hash: &str,
+    ) -> String {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!(
+            "../../../tests/fixtures/core/stream_transactions_with_proofs.json"
+        ))
+        .unwrap();
+        let mut proof = fixture["proofs"][0].clone();
+        proof["ledger_header"]["state_version"] = state_version.into();
+        proof["ledger_header"]["hashes"] = serde_json::json!({
+            "state_tree_hash": hash,
+            "transaction_tree_hash": hash,
+            "receipt_tree_hash": hash
+        });
+        let mut page: serde_json::Value =
+            serde_json::from_str(&transaction_page(20, versions)).unwrap();
+        page["proofs"] = serde_json::json!([proof]);
+        page.to_string()
+    }
+
+    #[test]
+    fn checks_pages_against_their_proofs() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok(
+            "stream/transactions",
+            &page_with_proof(&[5, 6], 6, "bad"),
+        );
+        mock.respond_ok(
+            "stream/transactions",
+            &page_with_proof(&[5, 6], 6, &format!("{:064x}", 6)),
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream("mainnet".to_string(), 5, 2)
+            .unwrap()
+            .include_proofs();
+
+        assert!(matches!(
+            stream.next_page(),
+            Err(CoreApiError::ProofMismatch(ProofMismatch::Hashes {
+                state_version: 6
+            }))
+        ));
+        assert_eq!(stream.last_seen_state_version, 4);
+        assert_eq!(versions(&stream.next_page().unwrap()), vec![5, 6]);
+        assert_eq!(stream.last_seen_state_version, 6);
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let client = CoreClientBlocking::new("http://mock".to_string());
+        assert_eq!(
+            client
+                .new_transaction_stream("mainnet".to_string(), 1, 10_001)
+                .unwrap_err(),
+            StreamConfigError::LimitPerPageTooLarge {
+                limit: 10_001,
+                max: MAX_LIMIT_PER_PAGE
+            }
+        );
+    }
+
+    #[test]
+    fn iterator_backs_off_after_errors() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond("stream/transactions", StatusCode::BAD_GATEWAY, "");
+        mock.respond("stream/transactions", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5]));
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .build_blocking()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream("mainnet".to_string(), 5, 2)
+            .unwrap()
+            .idle_poll_interval(Duration::from_millis(50));
+
+        assert!(stream.next().unwrap().is_err());
+        let started = std::time::Instant::now();
+        assert!(stream.next().unwrap().is_err());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        // The backoff doubles with every error in a row.
+        let started = std::time::Instant::now();
+        assert!(stream.next().unwrap().is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -1,2 +1,3 @@
+pub mod scrypto_call_preview;
 pub mod transaction_preview;
 pub mod transaction_submit;
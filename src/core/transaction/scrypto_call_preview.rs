@@ -0,0 +1,244 @@
+use self::core::{
+    builder::{RequestBuilderAsync, RequestBuilderBlocking},
+    error::CoreApiError,
+    match_response,
+    models::*,
+};
+use crate::*;
+use duplicate::duplicate_item;
+use maybe_async::*;
+use rust_decimal::Decimal;
+
+#[duplicate_item(
+    client_type                 maybe_async_attr ;
+    [ CoreClientAsync ]     [ must_be_async ];
+    [ CoreClientBlocking ]  [ must_be_sync ];
+  )]
+impl client_type {
+    /// Calls a function or method on the current ledger state without
+    /// committing anything, for example to read a value from a component.
+    #[maybe_async_attr]
+    pub async fn scrypto_call_preview(
+        &self,
+        request: ScryptoCallPreviewRequestBody,
+    ) -> Result<ScryptoCallPreview200ResponseBody, CoreApiError> {
+        let (text, status) =
+            self.post("transaction/call-preview", request).await?;
+        self.observe("transaction/call-preview", match_response(text, status))
+    }
+}
+
+impl TargetIdentifier {
+    pub fn function(
+        package_address: impl Into<String>,
+        blueprint_name: impl Into<String>,
+        function_name: impl Into<String>,
+    ) -> Self {
+        TargetIdentifier::Function {
+            package_address: package_address.into(),
+            blueprint_name: blueprint_name.into(),
+            function_name: function_name.into(),
+        }
+    }
+
+    pub fn method(
+        component_address: impl Into<String>,
+        method_name: impl Into<String>,
+    ) -> Self {
+        TargetIdentifier::Method {
+            component_address: component_address.into(),
+            method_name: method_name.into(),
+        }
+    }
+}
+
+// builder
+
+#[duplicate_item(
+    request_type client_type ;
+    [ RequestBuilderAsync ] [ CoreClientAsync ] ;
+    [ RequestBuilderBlocking ] [ CoreClientBlocking ] ;
+)]
+impl client_type {
+    /// Use [`TargetIdentifier::function`] or [`TargetIdentifier::method`]
+    /// for the target.
+    pub fn scrypto_call_preview_builder(
+        &self,
+        network: String,
+        target: TargetIdentifier,
+    ) -> request_type<ScryptoCallPreviewRequestBody> {
+        let request = ScryptoCallPreviewRequestBody {
+            network,
+            target,
+            arguments: vec![],
+        };
+        request_type {
+            client: self.clone(),
+            request,
+        }
+    }
+}
+
+#[duplicate_item(
+    builder_type maybe_async_attr;
+    [ RequestBuilderAsync ] [ must_be_async ];
+    [ RequestBuilderBlocking ] [ must_be_sync ];
+)]
+impl builder_type<ScryptoCallPreviewRequestBody> {
+    /// Adds an argument that is already encoded as Scrypto SBOR hex.
+    pub fn argument_hex(&mut self, value: String) -> &mut Self {
+        self.request.arguments.push(value);
+        self
+    }
+
+    pub fn arguments_hex(&mut self, value: Vec<String>) -> &mut Self {
+        self.request.arguments.extend(value);
+        self
+    }
+
+    pub fn argument_bool(&mut self, value: bool) -> &mut Self {
+        self.argument_hex(sbor_hex(0x01, &[value as u8]))
+    }
+
+    pub fn argument_u8(&mut self, value: u8) -> &mut Self {
+        self.argument_hex(sbor_hex(0x07, &value.to_le_bytes()))
+    }
+
+    pub fn argument_u32(&mut self, value: u32) -> &mut Self {
+        self.argument_hex(sbor_hex(0x09, &value.to_le_bytes()))
+    }
+
+    pub fn argument_u64(&mut self, value: u64) -> &mut Self {
+        self.argument_hex(sbor_hex(0x0a, &value.to_le_bytes()))
+    }
+
+    pub fn argument_i64(&mut self, value: i64) -> &mut Self {
+        self.argument_hex(sbor_hex(0x05, &value.to_le_bytes()))
+    }
+
+    pub fn argument_string(&mut self, value: &str) -> &mut Self {
+        let mut bytes = leb128(value.len());
+        bytes.extend_from_slice(value.as_bytes());
+        self.argument_hex(sbor_hex(0x0c, &bytes))
+    }
+
+    /// Adds a `Decimal`, rounded to 18 decimal places.
+    pub fn argument_decimal(&mut self, value: Decimal) -> &mut Self {
+        self.argument_hex(sbor_hex(0xa0, &decimal_attos(value)))
+    }
+
+    #[maybe_async_attr]
+    pub async fn fetch(
+        &self,
+    ) -> Result<ScryptoCallPreview200ResponseBody, CoreApiError> {
+        self.client.scrypto_call_preview(self.request.clone()).await
+    }
+}
+
+/// A Scrypto SBOR payload of a single value, hex-encoded.
+fn sbor_hex(value_kind: u8, value: &[u8]) -> String {
+    [0x5c, value_kind]
+        .iter()
+        .chain(value)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The length prefix of SBOR strings and collections.
+fn leb128(mut value: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// The value in units of 10^-18 as a little-endian 192-bit integer.
+fn decimal_attos(value: Decimal) -> [u8; 24] {
+    let value = value.round_dp(18);
+    let mantissa = value.mantissa().unsigned_abs();
+    let mut limbs = [mantissa as u64, (mantissa >> 64) as u64, 0];
+    for _ in value.scale()..18 {
+        let mut carry = 0u128;
+        for limb in limbs.iter_mut() {
+            let product = *limb as u128 * 10 + carry;
+            *limb = product as u64;
+            carry = product >> 64;
+        }
+    }
+    if value.is_sign_negative() && !value.is_zero() {
+        let mut carry = true;
+        for limb in limbs.iter_mut() {
+            let (sum, overflow) = (!*limb).overflowing_add(carry as u64);
+            *limb = sum;
+            carry = overflow;
+        }
+    }
+    let mut bytes = [0; 24];
+    for (chunk, limb) in bytes.chunks_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_le_bytes());
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decimal_attos, sbor_hex};
+    use crate::core::models::*;
+    use crate::transport::MockTransport;
+    use crate::CoreClientBuilder;
+    use rust_decimal::Decimal;
+    use std::rc::Rc;
+
+    #[test]
+    fn encodes_arguments_as_sbor() {
+        assert_eq!(
+            sbor_hex(0xa0, &decimal_attos(Decimal::ONE)),
+            format!("5ca0000064a7b3b6e00d{}", "0".repeat(32))
+        );
+        assert_eq!(
+            sbor_hex(0xa0, &decimal_attos(-Decimal::ONE)),
+            format!("5ca000009c584c491ff2{}", "f".repeat(32))
+        );
+    }
+
+    #[test]
+    fn previews_a_method_call() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok(
+            "transaction/call-preview",
+            include_str!(
+                "../../../tests/fixtures/core/scrypto_call_preview.json"
+            ),
+        );
+        let client = CoreClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let response = client
+            .scrypto_call_preview_builder(
+                "mainnet".to_string(),
+                TargetIdentifier::method("component_rdx1", "get_price"),
+            )
+            .argument_u8(1)
+            .argument_string("XRD")
+            .argument_bool(true)
+            .fetch()
+            .unwrap();
+
+        assert!(matches!(response.status, Status::Succeeded));
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[0].body).unwrap();
+        assert_eq!(body["target"]["type"], "Method");
+        assert_eq!(body["target"]["method_name"], "get_price");
+        assert_eq!(
+            body["arguments"],
+            serde_json::json!(["5c0701", "5c0c03585244", "5c0101"])
+        );
+    }
+}
@@ -20,7 +20,7 @@ impl client_type {
         request: TransactionPreviewRequestBody,
     ) -> Result<TransactionPreview200ResponseBody, CoreApiError> {
         let (text, status) = self.post("transaction/preview", request).await?;
-        match_response(text, status)
+        self.observe("transaction/preview", match_response(text, status))
     }
 }
 
@@ -32,6 +32,7 @@ impl client_type {
     [ RequestBuilderBlocking ] [ CoreClientBlocking ] ;
 )]
 impl client_type {
+    #[allow(clippy::too_many_arguments)]
     pub fn transaction_preview_builder(
         &self,
         manifest: String,
@@ -8,6 +8,91 @@ pub fn from_str<T>(
 where
     T: serde::de::DeserializeOwned,
 {
-    let deserializer = &mut serde_json::Deserializer::from_str(&text);
+    let deserializer = &mut serde_json::Deserializer::from_str(text);
     serde_path_to_error::deserialize(deserializer)
 }
+
+/// Parses a recorded response and serializes it again, failing if the
+/// result differs from the recorded JSON. Catches fields that the model
+/// drops, renames or changes on the way. Nulls are ignored, as a missing
+/// field and `null` both parse to `None`.
+#[cfg(test)]
+pub(crate) fn round_trip<T>(fixture: &str) -> T
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let parsed: T = from_str(fixture)
+        .unwrap_or_else(|e| panic!("could not parse fixture: {}", e));
+    let recorded: serde_json::Value = serde_json::from_str(fixture).unwrap();
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    if let Some(difference) =
+        difference(&without_nulls(serialized), &without_nulls(recorded), "")
+    {
+        panic!("serialized model differs from the fixture: {}", difference);
+    }
+    parsed
+}
+
+/// The path of the first value that differs, and both values.
+#[cfg(test)]
+fn difference(
+    serialized: &serde_json::Value,
+    recorded: &serde_json::Value,
+    path: &str,
+) -> Option<String> {
+    use serde_json::Value;
+    match (serialized, recorded) {
+        (Value::Object(serialized), Value::Object(recorded)) => recorded
+            .iter()
+            .map(|(key, value)| (key, serialized.get(key), Some(value)))
+            .chain(
+                serialized
+                    .iter()
+                    .filter(|(key, _)| !recorded.contains_key(*key))
+                    .map(|(key, value)| (key, Some(value), None)),
+            )
+            .find_map(|(key, serialized, recorded)| {
+                let path = format!("{}.{}", path, key);
+                match (serialized, recorded) {
+                    (Some(serialized), Some(recorded)) => {
+                        difference(serialized, recorded, &path)
+                    }
+                    (None, _) => Some(format!("{} is dropped", path)),
+                    (_, None) => Some(format!("{} is added", path)),
+                }
+            }),
+        (Value::Array(serialized), Value::Array(recorded))
+            if serialized.len() == recorded.len() =>
+        {
+            serialized.iter().zip(recorded).enumerate().find_map(
+                |(i, (serialized, recorded))| {
+                    difference(
+                        serialized,
+                        recorded,
+                        &format!("{}[{}]", path, i),
+                    )
+                },
+            )
+        }
+        _ => (serialized != recorded).then(|| {
+            format!("{} is {} instead of {}", path, serialized, recorded)
+        }),
+    }
+}
+
+#[cfg(test)]
+fn without_nulls(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.into_iter().map(without_nulls).collect())
+        }
+        value => value,
+    }
+}
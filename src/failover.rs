@@ -0,0 +1,249 @@
+//! # Failover
+//!
+//! Spreads the requests of a client over an ordered list of base URLs,
+//! e.g. a private Gateway with the public one as a fallback. Requests go
+//! to the first healthy endpoint. Network errors, `5xx` responses and
+//! `NotSyncedUpError`s count as failures and the request moves on to the
+//! next endpoint, as do responses rejected by a
+//! [`LedgerGuard`](crate::ledger_guard::LedgerGuard) for lagging behind.
+//! An endpoint that keeps failing is put in a cool-down and
+//! only tried again when all other endpoints fail or the cool-down ends.
+
+use crate::auth::redact_url;
+use crate::retry::is_not_synced_up;
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct Endpoints {
+    endpoints: Vec<Endpoint>,
+    cool_down: Duration,
+    failure_threshold: u32,
+}
+
+struct Endpoint {
+    base_url: String,
+    health: Mutex<Health>,
+}
+
+impl Debug for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("base_url", &redact_url(&self.base_url))
+            .field("health", &self.health)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    cooling_down_until: Option<Instant>,
+}
+
+impl Endpoints {
+    /// Endpoints in order of preference. Panics if the list is empty.
+    pub fn new(base_urls: Vec<String>) -> Self {
+        assert!(!base_urls.is_empty(), "at least one base url is required");
+        Endpoints {
+            endpoints: base_urls
+                .into_iter()
+                .map(|base_url| Endpoint {
+                    base_url,
+                    health: Mutex::new(Health::default()),
+                })
+                .collect(),
+            cool_down: Duration::from_secs(30),
+            failure_threshold: 1,
+        }
+    }
+
+    /// How long a failing endpoint is skipped. Defaults to 30 seconds.
+    pub fn cool_down(mut self, value: Duration) -> Self {
+        self.cool_down = value;
+        self
+    }
+
+    /// Consecutive failures after which an endpoint is put in a
+    /// cool-down. Defaults to 1.
+    pub fn failure_threshold(mut self, value: u32) -> Self {
+        self.failure_threshold = value.max(1);
+        self
+    }
+
+    pub fn base_urls(&self) -> Vec<&str> {
+        self.endpoints.iter().map(|e| e.base_url.as_str()).collect()
+    }
+
+    pub fn base_url(&self, index: usize) -> &str {
+        &self.endpoints[index].base_url
+    }
+
+    /// Whether the endpoint is currently skipped after failing.
+    pub fn is_cooling_down(&self, index: usize) -> bool {
+        let health = self.endpoints[index].health.lock().unwrap();
+        health
+            .cooling_down_until
+            .is_some_and(|until| until > Instant::now())
+    }
+
+    /// The order in which endpoints are tried for the next request:
+    /// healthy endpoints in order of preference, followed by the
+    /// endpoints in a cool-down, the one that recovers first leading.
+    pub fn candidates(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let mut cooling_down = vec![];
+        let mut candidates = vec![];
+        for (index, endpoint) in self.endpoints.iter().enumerate() {
+            match endpoint.health.lock().unwrap().cooling_down_until {
+                Some(until) if until > now => cooling_down.push((until, index)),
+                _ => candidates.push(index),
+            }
+        }
+        cooling_down.sort();
+        candidates.extend(cooling_down.into_iter().map(|(_, index)| index));
+        candidates
+    }
+
+    /// Records the outcome of a request to the endpoint and returns
+    /// whether it counts as a failure that should be retried elsewhere.
+    pub fn record<T>(
+        &self,
+        index: usize,
+        result: &Result<(String, StatusCode, T), TransportError>,
+    ) -> bool {
+        let failed = is_failure(result);
+        let mut health = self.endpoints[index].health.lock().unwrap();
+        if failed {
+            health.consecutive_failures += 1;
+            if health.consecutive_failures >= self.failure_threshold {
+                health.cooling_down_until =
+                    Some(Instant::now() + self.cool_down);
+            }
+        } else {
+            *health = Health::default();
+        }
+        failed
+    }
+}
+
+fn is_failure<T>(
+    result: &Result<(String, StatusCode, T), TransportError>,
+) -> bool {
+    match result {
+        Ok((text, status, _)) => {
+            status.is_server_error()
+                || (!status.is_success() && is_not_synced_up(text))
+        }
+        Err(TransportError::Network(_))
+        | Err(TransportError::StaleLedgerState { .. }) => true,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{MetricsHook, RequestMetrics};
+    use crate::transport::{MockTransport, TransportAsync};
+    use crate::GatewayClientBuilder;
+    use std::sync::Arc;
+
+    fn ok() -> Result<(String, StatusCode, ()), TransportError> {
+        Ok(("{}".to_string(), StatusCode::OK, ()))
+    }
+
+    fn bad_gateway() -> Result<(String, StatusCode, ()), TransportError> {
+        Ok((String::new(), StatusCode::BAD_GATEWAY, ()))
+    }
+
+    #[test]
+    fn failing_endpoints_cool_down() {
+        let endpoints = Endpoints::new(vec!["a".into(), "b".into()])
+            .failure_threshold(2)
+            .cool_down(Duration::from_millis(50));
+        assert_eq!(endpoints.candidates(), vec![0, 1]);
+        assert!(endpoints.record(0, &bad_gateway()));
+        assert_eq!(endpoints.candidates(), vec![0, 1]);
+        assert!(endpoints.record(0, &bad_gateway()));
+        assert!(endpoints.is_cooling_down(0));
+        assert_eq!(endpoints.candidates(), vec![1, 0]);
+        assert!(!endpoints.record(1, &ok()));
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(endpoints.candidates(), vec![0, 1]);
+    }
+
+    #[test]
+    fn client_errors_do_not_fail_over() {
+        let endpoints = Endpoints::new(vec!["a".into()]);
+        let not_found = Ok((String::new(), StatusCode::NOT_FOUND, ()));
+        assert!(!endpoints.record(0, &not_found));
+        let not_synced_up = Ok((
+            r#"{"details":{"type":"NotSyncedUpError"}}"#.to_string(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            (),
+        ));
+        assert!(endpoints.record(0, &not_synced_up));
+    }
+
+    #[tokio::test]
+    async fn client_fails_over_to_the_fallback() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond("status/gateway-status", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("status/gateway-status", "{}");
+        let transport: Arc<dyn TransportAsync> = mock.clone();
+        let client = GatewayClientBuilder::new("http://own".to_string())
+            .fallback("http://public".to_string())
+            .transport_async(transport)
+            .build_async()
+            .unwrap();
+
+        let (_, status) =
+            client.post("status/gateway-status", ()).await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        let urls: Vec<_> = mock.requests().into_iter().map(|r| r.url).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://own/status/gateway-status",
+                "http://public/status/gateway-status"
+            ]
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<RequestMetrics>>);
+
+    impl MetricsHook for Recorder {
+        fn on_request(&self, request: &RequestMetrics) {
+            self.0.lock().unwrap().push(request.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn responses_are_attributed_to_the_fallback() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond("status/gateway-status", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("status/gateway-status", "{}");
+        let recorder = Arc::new(Recorder::default());
+        let client = GatewayClientBuilder::new("http://own".to_string())
+            .fallback("http://public".to_string())
+            .transport_async(mock)
+            .metrics(recorder.clone())
+            .build_async()
+            .unwrap();
+
+        client.post("status/gateway-status", ()).await.unwrap();
+        let served_by: Vec<_> = recorder
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.base_url.clone())
+            .collect();
+        assert_eq!(served_by, vec![Some("http://public".to_string())]);
+    }
+}
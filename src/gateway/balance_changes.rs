@@ -0,0 +1,198 @@
+//! # Balance changes
+//!
+//! Net balance changes of a committed transaction, as returned with the
+//! `balance_changes` opt-in of `stream/transactions` and
+//! `transaction/committed-details`. Fee balance changes are reported
+//! separately by the Gateway and are included in the fungible totals here,
+//! so the net change of an account is what its balance actually moved by.
+
+use crate::gateway::models::*;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Non-fungible IDs that an entity gained or lost in a transaction.
+/// An ID that was both added and removed is in neither set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NonFungibleIdChanges {
+    pub added: BTreeSet<String>,
+    pub removed: BTreeSet<String>,
+}
+
+impl TransactionBalanceChanges {
+    /// Net fungible change per entity address and resource address,
+    /// fees included. Changes that cancel out are left out.
+    pub fn net_fungible_changes(
+        &self,
+    ) -> BTreeMap<String, BTreeMap<String, Decimal>> {
+        let fees = self.fungible_fee_balance_changes.iter().map(|change| {
+            (
+                &change.entity_address,
+                &change.resource_address,
+                change.balance_change,
+            )
+        });
+        let changes = self.fungible_balance_changes.iter().map(|change| {
+            (
+                &change.entity_address,
+                &change.resource_address,
+                change.balance_change,
+            )
+        });
+        let mut net: BTreeMap<String, BTreeMap<String, Decimal>> =
+            BTreeMap::new();
+        for (entity_address, resource_address, balance_change) in
+            fees.chain(changes)
+        {
+            *net.entry(entity_address.clone())
+                .or_default()
+                .entry(resource_address.clone())
+                .or_default() += balance_change;
+        }
+        for resources in net.values_mut() {
+            resources.retain(|_, change| !change.is_zero());
+        }
+        net.retain(|_, resources| !resources.is_empty());
+        net
+    }
+
+    /// Net fungible change per resource address for a single entity,
+    /// e.g. an account, fees included.
+    pub fn net_fungible_changes_of(
+        &self,
+        entity_address: &str,
+    ) -> BTreeMap<String, Decimal> {
+        self.net_fungible_changes()
+            .remove(entity_address)
+            .unwrap_or_default()
+    }
+
+    /// Net fungible change per resource address over all entities. This is
+    /// zero for resources that only moved between entities, and the amount
+    /// minted or burned otherwise.
+    pub fn net_fungible_changes_per_resource(
+        &self,
+    ) -> BTreeMap<String, Decimal> {
+        let mut net: BTreeMap<String, Decimal> = BTreeMap::new();
+        for resources in self.net_fungible_changes().into_values() {
+            for (resource_address, change) in resources {
+                *net.entry(resource_address).or_default() += change;
+            }
+        }
+        net.retain(|_, change| !change.is_zero());
+        net
+    }
+
+    /// Non-fungible IDs added and removed per entity address and
+    /// resource address.
+    pub fn net_non_fungible_changes(
+        &self,
+    ) -> BTreeMap<String, BTreeMap<String, NonFungibleIdChanges>> {
+        let mut net: BTreeMap<String, BTreeMap<String, NonFungibleIdChanges>> =
+            BTreeMap::new();
+        for change in &self.non_fungible_balance_changes {
+            let ids = net
+                .entry(change.entity_address.clone())
+                .or_default()
+                .entry(change.resource_address.clone())
+                .or_default();
+            for id in &change.added {
+                if !ids.removed.remove(id) {
+                    ids.added.insert(id.clone());
+                }
+            }
+            for id in &change.removed {
+                if !ids.added.remove(id) {
+                    ids.removed.insert(id.clone());
+                }
+            }
+        }
+        for resources in net.values_mut() {
+            resources.retain(|_, ids| {
+                !ids.added.is_empty() || !ids.removed.is_empty()
+            });
+        }
+        net.retain(|_, resources| !resources.is_empty());
+        net
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "account_rdx_alice";
+    const BOB: &str = "account_rdx_bob";
+    const XRD: &str = "resource_rdx_xrd";
+    const NFT: &str = "resource_rdx_nft";
+
+    fn fungible(
+        entity_address: &str,
+        resource_address: &str,
+        balance_change: &str,
+    ) -> TransactionFungibleBalanceChange {
+        TransactionFungibleBalanceChange {
+            entity_address: entity_address.to_string(),
+            resource_address: resource_address.to_string(),
+            balance_change: balance_change.parse().unwrap(),
+        }
+    }
+
+    fn changes() -> TransactionBalanceChanges {
+        TransactionBalanceChanges {
+            fungible_fee_balance_changes: vec![
+                TransactionFungibleFeeBalanceChange {
+                    change_type:
+                        TransactionFungibleFeeBalanceChangeType::FeePayment,
+                    entity_address: ALICE.to_string(),
+                    resource_address: XRD.to_string(),
+                    balance_change: "-0.25".parse().unwrap(),
+                },
+            ],
+            fungible_balance_changes: vec![
+                fungible(ALICE, XRD, "-100"),
+                fungible(BOB, XRD, "100"),
+                fungible(BOB, XRD, "-10"),
+                fungible(ALICE, XRD, "10"),
+            ],
+            non_fungible_balance_changes: vec![
+                TransactionNonFungibleBalanceChange {
+                    entity_address: BOB.to_string(),
+                    resource_address: NFT.to_string(),
+                    added: vec!["#1#".to_string(), "#2#".to_string()],
+                    removed: vec![],
+                },
+                TransactionNonFungibleBalanceChange {
+                    entity_address: BOB.to_string(),
+                    resource_address: NFT.to_string(),
+                    added: vec![],
+                    removed: vec!["#2#".to_string(), "#3#".to_string()],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn fungible_changes_are_netted_with_fees() {
+        let changes = changes();
+        let net = changes.net_fungible_changes();
+        assert_eq!(net[ALICE][XRD], "-90.25".parse().unwrap());
+        assert_eq!(net[BOB][XRD], Decimal::from(90));
+        assert_eq!(
+            changes.net_fungible_changes_of(BOB)[XRD],
+            Decimal::from(90)
+        );
+        // Only the fee left the accounts.
+        assert_eq!(
+            changes.net_fungible_changes_per_resource()[XRD],
+            "-0.25".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn non_fungible_ids_cancel_out() {
+        let net = changes().net_non_fungible_changes();
+        let ids = &net[BOB][NFT];
+        assert_eq!(ids.added, BTreeSet::from(["#1#".to_string()]));
+        assert_eq!(ids.removed, BTreeSet::from(["#3#".to_string()]));
+    }
+}
@@ -1,3 +1,7 @@
+use crate::gateway::stream::integrity::IntegrityError;
+use crate::gateway::stream::stream_client::StreamConfigError;
+use crate::metrics::ErrorVariant;
+use crate::transport::TransportError;
 use serde::Deserialize;
 use std::error::Error;
 use std::fmt::Debug;
@@ -30,6 +34,15 @@ pub enum GatewayApiError {
     },
     ClientError(GatewayApiErrorResponse),
     ServerError(GatewayApiErrorResponse),
+    /// The request failed in a custom transport or could not be serialized.
+    Transport(TransportError),
+    /// Loading or storing the checkpoint of a transaction stream failed.
+    Checkpoint(std::io::Error),
+    /// A transaction stream page had a missing or out-of-order
+    /// state version.
+    StreamIntegrity(IntegrityError),
+    /// A transaction stream was created with invalid arguments.
+    InvalidStream(StreamConfigError),
     Unknown,
 }
 
@@ -51,6 +64,16 @@ impl std::fmt::Display for GatewayApiError {
             GatewayApiError::ServerError(e) => {
                 write!(f, "Server error: {:?}", e)
             }
+            GatewayApiError::Transport(e) => write!(f, "{}", e),
+            GatewayApiError::Checkpoint(e) => {
+                write!(f, "Checkpoint error: {}", e)
+            }
+            GatewayApiError::StreamIntegrity(e) => {
+                write!(f, "Stream integrity error: {}", e)
+            }
+            GatewayApiError::InvalidStream(e) => {
+                write!(f, "Invalid stream: {}", e)
+            }
             GatewayApiError::Unknown => write!(f, "Unknown error"),
         }
     }
@@ -65,8 +88,40 @@ impl Debug for GatewayApiError {
 
 impl Error for GatewayApiError {}
 
+// The URL is dropped, some providers put the API key in it.
 impl From<reqwest::Error> for GatewayApiError {
     fn from(e: reqwest::Error) -> Self {
-        GatewayApiError::Network(e)
+        GatewayApiError::Network(e.without_url())
+    }
+}
+
+impl From<TransportError> for GatewayApiError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Network(e) => GatewayApiError::Network(e),
+            e => GatewayApiError::Transport(e),
+        }
+    }
+}
+
+impl From<StreamConfigError> for GatewayApiError {
+    fn from(e: StreamConfigError) -> Self {
+        GatewayApiError::InvalidStream(e)
+    }
+}
+
+impl ErrorVariant for GatewayApiError {
+    fn variant(&self) -> &'static str {
+        match self {
+            GatewayApiError::Network(_) => "Network",
+            GatewayApiError::Parsing { .. } => "Parsing",
+            GatewayApiError::ClientError(_) => "ClientError",
+            GatewayApiError::ServerError(_) => "ServerError",
+            GatewayApiError::Transport(_) => "Transport",
+            GatewayApiError::Checkpoint(_) => "Checkpoint",
+            GatewayApiError::StreamIntegrity(_) => "StreamIntegrity",
+            GatewayApiError::InvalidStream(_) => "InvalidStream",
+            GatewayApiError::Unknown => "Unknown",
+        }
     }
 }
@@ -1,3 +1,4 @@
+pub mod balance_changes;
 pub mod builder;
 pub mod error;
 pub mod models;
@@ -43,3 +44,47 @@ where
         _ => Err(GatewayApiError::Unknown),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::error::ErrorDetails;
+    use crate::gateway::models::GetGatewayStatus200Response;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn match_response_maps_status_codes() {
+        let error = r#"{
+            "message": "Entity not found",
+            "code": 404,
+            "details": { "type": "EntityNotFoundError" },
+            "trace_id": "abc"
+        }"#;
+        let result = match_response::<GetGatewayStatus200Response>(
+            error.to_string(),
+            StatusCode::NOT_FOUND,
+        );
+        match result {
+            Err(GatewayApiError::ClientError(response)) => {
+                assert_eq!(response.code, Some(404));
+                assert!(matches!(
+                    response.details,
+                    Some(ErrorDetails::EntityNotFoundError)
+                ));
+            }
+            other => panic!("Expected a client error, got {other:?}"),
+        }
+
+        let result = match_response::<GetGatewayStatus200Response>(
+            "not json".to_string(),
+            StatusCode::OK,
+        );
+        assert!(matches!(result, Err(GatewayApiError::Parsing { .. })));
+
+        let result = match_response::<GetGatewayStatus200Response>(
+            String::new(),
+            StatusCode::MOVED_PERMANENTLY,
+        );
+        assert!(matches!(result, Err(GatewayApiError::Unknown)));
+    }
+}
@@ -45,22 +45,36 @@ pub struct NonFungibleResourcesCollection {
     pub items: Vec<NonFungibleResourcesCollectionItem>,
 }
 
+// `amount` and `last_updated_at_state_version` are only set with the
+// Global aggregation level, `vaults` only with the Vault aggregation level.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FungibleResourcesCollectionItem {
     pub aggregation_level: AggregationLevel,
     pub resource_address: String,
     pub explicit_metadata: Option<EntityMetadataCollection>,
-    pub amount: u64,
-    pub last_updated_at_state_version: u64,
+    pub amount: Option<Decimal>,
+    pub last_updated_at_state_version: Option<u64>,
+    pub vaults: Option<FungibleResourcesCollectionItemVaultAggregatedVault>,
 }
 
+// Same as above, `amount` is the number of non fungibles held.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NonFungibleResourcesCollectionItem {
+    pub aggregation_level: AggregationLevel,
     pub resource_address: String,
-    pub total_count: u64,
+    pub explicit_metadata: Option<EntityMetadataCollection>,
+    pub amount: Option<u64>,
+    pub last_updated_at_state_version: Option<u64>,
+    pub vaults: Option<NonFungibleResourcesCollectionItemVaultAggregatedVault>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct NonFungibleResourcesCollectionItemVaultAggregatedVault {
+    pub total_count: Option<u64>,
     pub next_cursor: Option<String>,
     pub items: Vec<NonFungibleResourcesCollectionItemVaultAggregatedVaultItem>,
 }
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct EntityMetadataCollection {
     pub total_count: Option<u64>,
@@ -80,6 +94,9 @@ pub struct EntityMetadataItem {
 pub struct EntityMetadataItemValue {
     pub raw_hex: String,
     pub programmatic_json: serde_json::Value,
+    /// The value tagged with its metadata `type`, e.g. `String` or
+    /// `GlobalAddress`.
+    pub typed: serde_json::Value,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -223,7 +240,44 @@ pub enum PackageVmType {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ComponentEntityRoleAssignments {
-    // This type is sadly not implemented at this time.
+    /// The owner role and who may update it. Kept as JSON, the Gateway
+    /// schema does not type it either.
+    pub owner: serde_json::Value,
+    pub entries: Vec<ComponentEntityRoleAssignmentEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ComponentEntityRoleAssignmentEntry {
+    pub role_key: RoleKey,
+    pub assignment: ComponentEntityRoleAssignmentEntryAssignment,
+    pub updater_roles: Option<Vec<RoleKey>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct RoleKey {
+    pub module: ObjectModuleId,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ObjectModuleId {
+    Main,
+    Metadata,
+    Royalty,
+    RoleAssignment,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ComponentEntityRoleAssignmentEntryAssignment {
+    pub resolution: RoleAssignmentResolution,
+    /// The access rule, when the role is not resolved to the owner.
+    pub explicit_rule: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum RoleAssignmentResolution {
+    Explicit,
+    Owner,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -310,23 +364,13 @@ pub struct PublicKey {
     pub key_hex: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct PreviewTransactionFlags {
     pub use_free_credit: bool,
     pub assume_all_signature_proofs: bool,
     pub skip_epoch_check: bool,
 }
 
-impl Default for PreviewTransactionFlags {
-    fn default() -> Self {
-        PreviewTransactionFlags {
-            use_free_credit: false,
-            assume_all_signature_proofs: false,
-            skip_epoch_check: false,
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct TransactionPreviewRequestBody {
     pub manifest: String,
@@ -344,7 +388,7 @@ pub struct TransactionPreviewRequestBody {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionPreview200ResponseBody {
     pub encoded_receipt: String,
-    pub receipt: Receipt,
+    pub receipt: PreviewReceipt,
     pub resource_changes: Vec<InstructionResourceChanges>,
     pub logs: Vec<Log>,
 }
@@ -383,6 +427,36 @@ pub struct Receipt {
     pub error_message: Option<String>,
 }
 
+// The Gateway passes the Core API receipt of a preview through unchanged,
+// which differs from the receipt of a committed transaction in its status
+// and events.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreviewReceipt {
+    pub status: PreviewReceiptStatus,
+    pub fee_summary: FeeSummary,
+    pub costing_parameters: CostingParameters,
+    pub fee_source: Option<FeeSource>,
+    pub fee_destination: Option<FeeDestination>,
+    pub state_updates: StateUpdates,
+    pub events: Option<Vec<PreviewEvent>>,
+    pub next_epoch: Option<NextEpoch>,
+    pub output: Option<Vec<SborData>>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PreviewReceiptStatus {
+    Succeeded,
+    Failed,
+    Rejected,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PreviewEvent {
+    pub r#type: EventTypeIdentifier,
+    pub data: SborData,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NextEpoch {
     pub epoch: u64,
@@ -555,14 +629,86 @@ pub struct CostingParameters {
     pub tip_percentage: u8,
 }
 
-// Cutting some corners here until I ever need this.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct StateUpdates {
-    pub deleted_partitions: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub created_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub updated_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub deleted_substates: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
-    pub new_global_entities: Vec<serde_json::Value>, // Assuming it's a list of generic JSON values
+    pub deleted_partitions: Vec<PartitionId>,
+    pub created_substates: Vec<CreatedSubstate>,
+    pub updated_substates: Vec<UpdatedSubstate>,
+    pub deleted_substates: Vec<DeletedSubstate>,
+    pub new_global_entities: Vec<EntityReference>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PartitionId {
+    pub entity_type: EntityType,
+    pub entity_address: String,
+    pub partition_number: u8,
+    pub partition_kind: PartitionKind,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PartitionKind {
+    Field,
+    KeyValue,
+    Index,
+    SortedIndex,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubstateId {
+    pub entity_type: EntityType,
+    pub entity_address: String,
+    pub partition_number: u8,
+    pub partition_kind: PartitionKind,
+    /// E.g. `FungibleVaultFieldBalance`. Kept as a string, new substate
+    /// types are added with every protocol update.
+    pub substate_type: String,
+    pub substate_key: SubstateKey,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "key_type")]
+pub enum SubstateKey {
+    Field {
+        id: u8,
+    },
+    Map {
+        key_hex: String,
+    },
+    Sorted {
+        sort_prefix_hex: String,
+        key_hex: String,
+    },
+}
+
+// The substate data and system structure are unions of many substate
+// types, they are kept as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubstateValue {
+    pub substate_hex: Option<String>,
+    pub substate_data: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreatedSubstate {
+    pub substate_id: SubstateId,
+    pub value: SubstateValue,
+    pub system_structure: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdatedSubstate {
+    pub substate_id: SubstateId,
+    pub new_value: SubstateValue,
+    pub previous_value: Option<SubstateValue>,
+    pub system_structure: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeletedSubstate {
+    pub substate_id: SubstateId,
+    pub previous_value: Option<SubstateValue>,
+    pub system_structure: serde_json::Value,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -575,6 +721,82 @@ pub struct Transactionsubmit200ResponseBody {
     pub duplicate: bool,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionStatusRequestBody {
+    pub intent_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionStatus200ResponseBody {
+    pub ledger_state: LedgerState,
+    pub status: TransactionStatus,
+    pub intent_status: TransactionIntentStatus,
+    pub intent_status_description: String,
+    pub known_payloads: Vec<TransactionStatusResponseKnownPayloadItem>,
+    pub committed_state_version: Option<u64>,
+    /// The epoch after which the intent can no longer be committed.
+    pub permanently_rejects_at_epoch: Option<u64>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionStatusResponseKnownPayloadItem {
+    pub payload_hash: String,
+    pub status: TransactionStatus,
+    pub payload_status: Option<TransactionPayloadStatus>,
+    pub payload_status_description: Option<String>,
+    pub error_message: Option<String>,
+    pub latest_error_message: Option<String>,
+    pub handling_status: Option<TransactionPayloadGatewayHandlingStatus>,
+    pub handling_status_reason: Option<String>,
+    pub submission_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TransactionCommittedDetailsRequestBody {
+    pub at_ledger_state: Option<LedgerStateSelector>,
+    pub intent_hash: String,
+    pub opt_ins: Option<TransactionDetailsOptIns>,
+}
+
+/// The Gateway takes the same opt-ins for the details
+/// of a transaction as for the transaction stream.
+pub type TransactionDetailsOptIns = TransactionStreamOptIns;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionCommittedDetails200ResponseBody {
+    pub ledger_state: LedgerState,
+    pub transaction: CommittedTransactionInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionIntentStatus {
+    Unknown,
+    CommittedSuccess,
+    CommittedFailure,
+    CommitPendingOutcomeUnknown,
+    PermanentlyRejected,
+    LikelyButNotCertainRejection,
+    Pending,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionPayloadStatus {
+    Unknown,
+    CommittedSuccess,
+    CommittedFailure,
+    CommitPendingOutcomeUnknown,
+    PermanentlyRejected,
+    TemporarilyRejected,
+    Pending,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionPayloadGatewayHandlingStatus {
+    HandlingSubmission,
+    Concluded,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GetKeyValueStoreKeysRequestBody {
     pub at_ledger_state: Option<LedgerStateSelector>,
@@ -645,7 +867,7 @@ pub struct ReleaseInfo {
     pub image_tag: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct TransactionStreamRequestBody {
     pub at_ledger_state: Option<LedgerStateSelector>,
     pub from_ledger_state: Option<LedgerStateSelector>,
@@ -656,28 +878,37 @@ pub struct TransactionStreamRequestBody {
     pub manifest_accounts_deposited_into_filter: Option<Vec<String>>,
     pub manifest_resources_filter: Option<Vec<String>>,
     pub affected_global_entities_filter: Option<Vec<String>>,
-    // Not implemented now.
-    // pub events_filter
+    pub manifest_badges_presented_filter: Option<Vec<String>>,
+    pub accounts_with_manifest_owner_method_calls: Option<Vec<String>>,
+    pub accounts_without_manifest_owner_method_calls: Option<Vec<String>>,
+    pub manifest_class_filter: Option<ManifestClassFilter>,
+    pub events_filter: Option<Vec<StreamTransactionsRequestEventFilterItem>>,
+    pub event_global_emitters_filter: Option<Vec<String>>,
     pub order: Option<Order>,
     pub opt_ins: Option<TransactionStreamOptIns>,
 }
 
-impl Default for TransactionStreamRequestBody {
-    fn default() -> Self {
-        TransactionStreamRequestBody {
-            at_ledger_state: None,
-            from_ledger_state: None,
-            cursor: None,
-            limit_per_page: None,
-            kind_filter: None,
-            manifest_accounts_withdrawn_from_filter: None,
-            manifest_accounts_deposited_into_filter: None,
-            manifest_resources_filter: None,
-            affected_global_entities_filter: None,
-            order: None,
-            opt_ins: None,
-        }
-    }
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestClassFilter {
+    pub class: ManifestClass,
+    // Only match transactions for which the class
+    // is the most specific one.
+    pub match_only_most_specific: bool,
+}
+
+// Both addresses are optional, an item without
+// them matches every event of the kind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StreamTransactionsRequestEventFilterItem {
+    pub event: StreamTransactionsRequestEventFilterItemEvent,
+    pub emitter_address: Option<String>,
+    pub resource_address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum StreamTransactionsRequestEventFilterItemEvent {
+    Deposit,
+    Withdrawal,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -738,8 +969,47 @@ pub struct CommittedTransactionInfo {
     // This field will be present only for user transactions.
     pub manifest_classes: Option<Vec<ManifestClass>>,
     pub message: Option<TransactionMessage>,
-    // not implemented now
-    // pub balance_changes: Option<Vec<TransactionBalanceChanges>>,
+    pub balance_changes: Option<TransactionBalanceChanges>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct TransactionBalanceChanges {
+    pub fungible_fee_balance_changes: Vec<TransactionFungibleFeeBalanceChange>,
+    // Balance changes excluding fees.
+    pub fungible_balance_changes: Vec<TransactionFungibleBalanceChange>,
+    pub non_fungible_balance_changes: Vec<TransactionNonFungibleBalanceChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionFungibleFeeBalanceChange {
+    #[serde(rename = "type")]
+    pub change_type: TransactionFungibleFeeBalanceChangeType,
+    pub entity_address: String,
+    pub resource_address: String,
+    pub balance_change: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TransactionFungibleFeeBalanceChangeType {
+    FeePayment,
+    FeeDistributed,
+    TipDistributed,
+    RoyaltyDistributed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionFungibleBalanceChange {
+    pub entity_address: String,
+    pub resource_address: String,
+    pub balance_change: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransactionNonFungibleBalanceChange {
+    pub entity_address: String,
+    pub resource_address: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -782,3 +1052,185 @@ pub enum ManifestClass {
     ValidatorClaim,
     AccountDepositSettingsUpdate,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deserialize::round_trip;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!("../../tests/fixtures/gateway/", $name))
+        };
+    }
+
+    #[test]
+    fn gateway_status() {
+        let res: GetGatewayStatus200Response =
+            round_trip(fixture!("status_gateway_status.json"));
+        assert_eq!(res.ledger_state.network, "mainnet");
+        assert!(!res.release_info.release_version.is_empty());
+    }
+
+    #[test]
+    fn state_entity_details() {
+        let res: StateEntityDetails200Response =
+            round_trip(fixture!("state_entity_details.json"));
+        let component = &res.items[0];
+        let fungible = &component.fungible_resources.as_ref().unwrap().items[0];
+        assert_eq!(fungible.aggregation_level, AggregationLevel::Global);
+        assert_eq!(
+            fungible.amount,
+            Some("153823.781927341295023117".parse().unwrap())
+        );
+        let non_fungible =
+            &component.non_fungible_resources.as_ref().unwrap().items[0];
+        assert_eq!(non_fungible.amount, Some(1));
+        let Some(StateEntityDetailsResponseItemDetails::Component(details)) =
+            &component.details
+        else {
+            panic!("expected component details");
+        };
+        let entry = &details.role_assignments.as_ref().unwrap().entries[0];
+        assert_eq!(entry.role_key.name, "admin");
+        assert_eq!(
+            entry.assignment.resolution,
+            RoleAssignmentResolution::Owner
+        );
+        assert!(matches!(
+            res.items[1].details,
+            Some(StateEntityDetailsResponseItemDetails::FungibleResource(_))
+        ));
+    }
+
+    #[test]
+    fn state_entity_fungibles_page() {
+        let res: StateEntityFungiblesPage200Response =
+            round_trip(fixture!("state_entity_fungibles_page.json"));
+        let item = &res.items[0];
+        assert_eq!(item.aggregation_level, AggregationLevel::Vault);
+        assert_eq!(item.amount, None);
+        let vault = &item.vaults.as_ref().unwrap().items[0];
+        assert_eq!(
+            vault.amount,
+            "25016.447710012018462811".parse::<Decimal>().unwrap()
+        );
+    }
+
+    #[test]
+    fn state_keyvaluestore() {
+        let keys: GetKeyValueStoreKeys200ResponseBody =
+            round_trip(fixture!("state_keyvaluestore_keys.json"));
+        assert!(!keys.items.is_empty());
+        let data: GetKeyValueStoreData200ResponseBody =
+            round_trip(fixture!("state_keyvaluestore_data.json"));
+        assert_eq!(data.key_value_store_address, keys.key_value_store_address);
+        assert!(!data.entries.is_empty());
+    }
+
+    #[test]
+    fn transaction_preview() {
+        let res: TransactionPreview200ResponseBody =
+            round_trip(fixture!("transaction_preview.json"));
+        assert_eq!(res.receipt.status, PreviewReceiptStatus::Succeeded);
+        let events = res.receipt.events.unwrap();
+        assert_eq!(events[0].r#type.name, "SwapEvent");
+        assert_eq!(res.receipt.output.unwrap().len(), 2);
+        let updates = &res.receipt.state_updates;
+        assert_eq!(
+            updates.deleted_substates[0].substate_id.substate_key,
+            SubstateKey::Sorted {
+                sort_prefix_hex: "0001".to_string(),
+                key_hex: "5c2100".to_string(),
+            }
+        );
+        assert!(updates.updated_substates[0].previous_value.is_none());
+        assert_eq!(
+            res.resource_changes[0].resource_changes[0].amount,
+            Decimal::from(100)
+        );
+        assert_eq!(res.logs.len(), 1);
+    }
+
+    #[test]
+    fn transaction_submit() {
+        let res: Transactionsubmit200ResponseBody =
+            round_trip(fixture!("transaction_submit.json"));
+        assert!(!res.duplicate);
+    }
+
+    #[test]
+    fn transaction_status() {
+        let res: TransactionStatus200ResponseBody =
+            round_trip(fixture!("transaction_status.json"));
+        assert_eq!(
+            res.intent_status,
+            TransactionIntentStatus::CommittedSuccess
+        );
+        assert_eq!(res.committed_state_version, Some(100246366));
+        assert_eq!(
+            res.known_payloads[0].handling_status,
+            Some(TransactionPayloadGatewayHandlingStatus::Concluded)
+        );
+    }
+
+    #[test]
+    fn transaction_committed_details() {
+        let res: TransactionCommittedDetails200ResponseBody =
+            round_trip(fixture!("transaction_committed_details.json"));
+        assert_eq!(res.transaction.state_version, 100246366);
+        assert!(res.transaction.raw_hex.is_some());
+        assert!(res
+            .transaction
+            .manifest_instructions
+            .unwrap()
+            .starts_with("CALL_METHOD"));
+        let balance_changes = res.transaction.balance_changes.unwrap();
+        assert_eq!(
+            balance_changes.fungible_fee_balance_changes[0].change_type,
+            TransactionFungibleFeeBalanceChangeType::FeePayment
+        );
+        assert_eq!(
+            balance_changes.fungible_balance_changes[1].balance_change,
+            Decimal::from(100)
+        );
+        assert_eq!(
+            balance_changes.non_fungible_balance_changes[0].added.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stream_transactions() {
+        let res: TransactionStream200ResponseBody =
+            round_trip(fixture!("stream_transactions.json"));
+        assert_eq!(res.items.len(), 2);
+        let success = &res.items[0];
+        assert_eq!(
+            success.transaction_status,
+            TransactionStatus::CommittedSuccess
+        );
+        assert_eq!(success.fee_paid, Some("0.35884239778".parse().unwrap()));
+        let events = success
+            .receipt
+            .as_ref()
+            .and_then(|receipt| receipt.events.as_ref())
+            .unwrap();
+        assert_eq!(events[1].name, "DepositEvent");
+        assert_eq!(
+            success.message,
+            Some(TransactionMessage::Plaintext {
+                mime_type: "text/plain".to_string(),
+                content: PlaintextMessageContent::String {
+                    value: "Swap via Ociswap".to_string()
+                },
+            })
+        );
+        let failure = &res.items[1];
+        assert_eq!(
+            failure.transaction_status,
+            TransactionStatus::CommittedFailure
+        );
+        assert!(failure.error_message.is_some());
+    }
+}
@@ -21,7 +21,7 @@ impl client_type {
         request: StateEntityDetailsRequest,
     ) -> Result<StateEntityDetails200Response, GatewayApiError> {
         let (text, status) = self.post("state/entity/details", request).await?;
-        match_response(text, status)
+        self.observe("state/entity/details", match_response(text, status))
     }
 }
 
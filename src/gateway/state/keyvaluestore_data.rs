@@ -23,7 +23,7 @@ impl client_type {
     ) -> Result<GetKeyValueStoreData200ResponseBody, GatewayApiError> {
         let (text, status) =
             self.post("state/key-value-store/data", request).await?;
-        match_response(text, status)
+        self.observe("state/key-value-store/data", match_response(text, status))
     }
 }
 
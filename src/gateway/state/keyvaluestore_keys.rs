@@ -22,7 +22,7 @@ impl client_type {
     ) -> Result<GetKeyValueStoreKeys200ResponseBody, GatewayApiError> {
         let (text, status) =
             self.post("state/key-value-store/keys", request).await?;
-        match_response(text, status)
+        self.observe("state/key-value-store/keys", match_response(text, status))
     }
 }
 
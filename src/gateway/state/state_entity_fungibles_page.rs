@@ -22,7 +22,10 @@ impl client_type {
     ) -> Result<StateEntityFungiblesPage200Response, GatewayApiError> {
         let (text, status) =
             self.post("state/entity/page/fungibles", request).await?;
-        match_response(text, status)
+        self.observe(
+            "state/entity/page/fungibles",
+            match_response(text, status),
+        )
     }
 }
 
@@ -16,6 +16,6 @@ impl client_type {
         let (text, status) = self
             .post("status/gateway-status", serde_json::Value::Null)
             .await?;
-        match_response(text, status)
+        self.observe("status/gateway-status", match_response(text, status))
     }
 }
@@ -0,0 +1,155 @@
+//! # Integrity
+//!
+//! Without any filter, every state version of the ledger is a transaction,
+//! so a [`TransactionStreamAsync`](super::stream_client::TransactionStreamAsync)
+//! or [`TransactionStreamBlocking`](super::stream_client::TransactionStreamBlocking)
+//! must deliver contiguous state versions. With an [`IntegrityMode`] other
+//! than `Off`, the stream verifies every page and either returns an
+//! [`IntegrityError`] or fetches the page again when a state version is
+//! missing or out of order. The check is skipped while any filter is set,
+//! including the `User` kind filter the stream starts with, since filtered
+//! pages have gaps by design.
+
+use crate::gateway::models::*;
+use std::fmt::Display;
+
+/// How many times a page is fetched again in `Refetch`
+/// mode before the error is returned.
+pub const MAX_REFETCHES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegrityMode {
+    /// Pages are not verified.
+    #[default]
+    Off,
+    /// A page with a gap or out-of-order state version
+    /// is returned as an error.
+    Error,
+    /// A page with a gap or out-of-order state version is fetched again,
+    /// up to [`MAX_REFETCHES`] times, before it is returned as an error.
+    Refetch,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The state versions after `after` and before `next` are missing.
+    Gap { after: u64, next: u64 },
+    /// `found` came after `previous` on the same page.
+    OutOfOrder { previous: u64, found: u64 },
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            IntegrityError::Gap { after, next } => write!(
+                f,
+                "state versions {} to {} are missing",
+                after + 1,
+                next - 1
+            ),
+            IntegrityError::OutOfOrder { previous, found } => {
+                write!(f, "state version {} came after {}", found, previous)
+            }
+        }
+    }
+}
+
+/// What a stream has seen so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamCounters {
+    /// Transactions that were delivered before and were dropped.
+    pub duplicates_filtered: u64,
+    /// Pages with a gap or out-of-order state version.
+    pub integrity_violations: u64,
+    /// Pages that were fetched again after a violation.
+    pub refetches: u64,
+}
+
+/// Whether every state version of the ledger is streamed.
+pub fn is_unfiltered(request: &TransactionStreamRequestBody) -> bool {
+    request.kind_filter == Some(TransactionKindFilter::All)
+        && request.manifest_accounts_withdrawn_from_filter.is_none()
+        && request.manifest_accounts_deposited_into_filter.is_none()
+        && request.manifest_resources_filter.is_none()
+        && request.affected_global_entities_filter.is_none()
+        && request.manifest_badges_presented_filter.is_none()
+        && request.accounts_with_manifest_owner_method_calls.is_none()
+        && request
+            .accounts_without_manifest_owner_method_calls
+            .is_none()
+        && request.manifest_class_filter.is_none()
+        && request.events_filter.is_none()
+        && request.event_global_emitters_filter.is_none()
+}
+
+/// Verifies that the state versions of a page are increasing and that the
+/// ones after `last_seen_state_version` follow it without gaps, up to
+/// `range_end` for a prefetched page.
+pub fn verify(
+    items: &[CommittedTransactionInfo],
+    last_seen_state_version: u64,
+    range_end: Option<u64>,
+) -> Result<(), IntegrityError> {
+    for pair in items.windows(2) {
+        if pair[1].state_version <= pair[0].state_version {
+            return Err(IntegrityError::OutOfOrder {
+                previous: pair[0].state_version,
+                found: pair[1].state_version,
+            });
+        }
+    }
+    let mut previous = last_seen_state_version;
+    for item in items
+        .iter()
+        .filter(|item| item.state_version > last_seen_state_version)
+    {
+        if item.state_version != previous + 1 {
+            return Err(IntegrityError::Gap {
+                after: previous,
+                next: item.state_version,
+            });
+        }
+        previous = item.state_version;
+    }
+    match range_end {
+        Some(range_end) if previous < range_end => Err(IntegrityError::Gap {
+            after: previous,
+            next: range_end + 1,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(versions: &[u64]) -> Vec<CommittedTransactionInfo> {
+        let page =
+            crate::gateway::stream::test_utils::transaction_page(20, versions);
+        serde_json::from_str::<TransactionStream200ResponseBody>(&page)
+            .unwrap()
+            .items
+    }
+
+    #[test]
+    fn verifies_contiguous_state_versions() {
+        assert_eq!(verify(&items(&[4, 5, 6]), 4, None), Ok(()));
+        assert_eq!(verify(&items(&[]), 4, None), Ok(()));
+        assert_eq!(
+            verify(&items(&[5, 7]), 4, None),
+            Err(IntegrityError::Gap { after: 5, next: 7 })
+        );
+        assert_eq!(
+            verify(&items(&[5, 6]), 4, Some(8)),
+            Err(IntegrityError::Gap { after: 6, next: 9 })
+        );
+        assert_eq!(
+            verify(&items(&[5, 6, 5]), 4, None),
+            Err(IntegrityError::OutOfOrder {
+                previous: 6,
+                found: 5
+            })
+        );
+    }
+}
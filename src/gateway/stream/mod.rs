@@ -1,2 +1,44 @@
+pub mod backfill;
+pub mod integrity;
+pub mod prefetch;
 pub mod stream_client;
 pub mod transactions_stream;
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use serde_json::json;
+
+    /// A `stream/transactions` response with one transaction
+    /// for each of the given state versions.
+    pub fn transaction_page(
+        ledger_state_version: u64,
+        versions: &[u64],
+    ) -> String {
+        let items: Vec<_> = versions
+            .iter()
+            .map(|state_version| {
+                json!({
+                    "state_version": state_version,
+                    "epoch": 100,
+                    "round": state_version % 1000,
+                    "round_timestamp": "2024-01-01T00:00:00.000Z",
+                    "transaction_status": "CommittedSuccess",
+                    "payload_hash": format!("txid_{state_version}"),
+                    "intent_hash": format!("txid_{state_version}"),
+                    "fee_paid": "0.25"
+                })
+            })
+            .collect();
+        json!({
+            "ledger_state": {
+                "network": "mainnet",
+                "state_version": ledger_state_version,
+                "proposer_round_timestamp": "2024-01-01T00:00:00.000Z",
+                "epoch": 100,
+                "round": 10
+            },
+            "items": items
+        })
+        .to_string()
+    }
+}
@@ -0,0 +1,151 @@
+//! # Prefetch
+//!
+//! Keeps several `stream/transactions` requests of a
+//! [`TransactionStreamAsync`](super::stream_client::TransactionStreamAsync)
+//! in flight while it is behind the ledger tip. Every prefetched request
+//! covers a disjoint range of `limit_per_page` state versions, from its
+//! `from_ledger_state` up to its `at_ledger_state`. A range never holds
+//! more transactions than fit on one page, so every page is complete and
+//! the pages can be delivered in order without gaps. Ranges that would
+//! reach the last known ledger tip are not prefetched, the stream then
+//! fetches one page at a time again. Since a range ends below the tip,
+//! the stream can continue right after it.
+
+use crate::gateway::builder::RequestBuilderAsync;
+use crate::gateway::error::GatewayApiError;
+use crate::gateway::models::*;
+use crate::gateway::stream::stream_client::MAX_LIMIT_PER_PAGE;
+use crate::transport::BoxFuture;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+type PageResult = Result<TransactionStream200ResponseBody, GatewayApiError>;
+
+#[derive(Debug, Default)]
+pub(crate) struct Pipeline {
+    /// Number of prefetched requests, 0 to fetch one page at a time.
+    pub depth: usize,
+    /// The highest ledger state version seen in a response.
+    pub ledger_tip: u64,
+    pages: VecDeque<PendingPage>,
+}
+
+/// A page request in flight.
+struct PendingPage {
+    /// The last state version of the range, or `None` when
+    /// the request is not limited to a range.
+    range_end: Option<u64>,
+    future: BoxFuture<'static, PageResult>,
+    result: Option<PageResult>,
+}
+
+impl Debug for PendingPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PendingPage")
+            .field("range_end", &self.range_end)
+            .field("done", &self.result.is_some())
+            .finish()
+    }
+}
+
+impl Pipeline {
+    /// Sends requests until `depth` ranges are in flight, starting at
+    /// `from_state_version`, or a single request without a range if
+    /// none can be prefetched and nothing is in flight. That request
+    /// waits for `delay` first.
+    pub fn fill(
+        &mut self,
+        builder: &RequestBuilderAsync<TransactionStreamRequestBody>,
+        from_state_version: u64,
+        delay: Option<Duration>,
+    ) {
+        // Ranges are only sent after one another.
+        if self
+            .pages
+            .back()
+            .is_some_and(|page| page.range_end.is_none())
+        {
+            return;
+        }
+        // The stream validated the page size when it was created.
+        let range = u64::from(
+            builder.request.limit_per_page.unwrap_or(MAX_LIMIT_PER_PAGE),
+        );
+        let mut from_state_version = self
+            .pages
+            .back()
+            .and_then(|page| page.range_end)
+            .map(|range_end| range_end + 1)
+            .unwrap_or(from_state_version);
+        while self.pages.len() < self.depth
+            && from_state_version + range - 1 < self.ledger_tip
+        {
+            let range_end = from_state_version + range - 1;
+            let mut builder = builder.clone();
+            builder
+                .from_state_version(from_state_version)
+                .at_state_version(range_end);
+            self.send(builder, Some(range_end), None);
+            from_state_version = range_end + 1;
+        }
+        if self.pages.is_empty() {
+            self.send(builder.clone(), None, delay);
+        }
+    }
+
+    fn send(
+        &mut self,
+        builder: RequestBuilderAsync<TransactionStreamRequestBody>,
+        range_end: Option<u64>,
+        delay: Option<Duration>,
+    ) {
+        let future = Box::pin(async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            builder.fetch().await
+        });
+        self.pages.push_back(PendingPage {
+            range_end,
+            future,
+            result: None,
+        });
+    }
+
+    /// Drops all requests in flight.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Drives all requests in flight and returns the oldest one once it
+    /// is done, with the end of its range. After an error, the requests
+    /// for later ranges are dropped and sent again on the next `fill`.
+    pub fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<(Option<u64>, PageResult)> {
+        for page in self.pages.iter_mut() {
+            if page.result.is_none() {
+                if let Poll::Ready(result) = page.future.as_mut().poll(cx) {
+                    page.result = Some(result);
+                }
+            }
+        }
+        let Some(result) =
+            self.pages.front_mut().and_then(|page| page.result.take())
+        else {
+            return Poll::Pending;
+        };
+        let range_end = self.pages.pop_front().unwrap().range_end;
+        match &result {
+            Ok(response) => {
+                self.ledger_tip =
+                    self.ledger_tip.max(response.ledger_state.state_version);
+            }
+            Err(_) => self.pages.clear(),
+        }
+        Poll::Ready((range_end, result))
+    }
+}
@@ -1,43 +1,89 @@
+use crate::checkpoint::{CheckpointMode, CheckpointStore};
 use crate::gateway::builder::RequestBuilderAsync;
 use crate::gateway::builder::RequestBuilderBlocking;
 use crate::gateway::error::GatewayApiError;
 use crate::gateway::models::*;
+use crate::gateway::stream::integrity::*;
+use crate::gateway::stream::prefetch::Pipeline;
+pub use crate::stream_config::StreamConfigError;
+use crate::stream_config::{error_backoff, validate};
 use crate::GatewayClientAsync;
 use crate::GatewayClientBlocking;
 use duplicate::duplicate_item;
+use futures_core::Stream;
 use maybe_async::{must_be_async, must_be_sync};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The largest page the Gateway returns, which is also
+/// its page size when `limit_per_page` is not set.
+pub const MAX_LIMIT_PER_PAGE: u32 = 100;
 
 /// A managed stream client that starts at a specific state version and fetches transactions
-/// in chronological order. It allows for easy fetching of the next page with `.next()`.
+/// in chronological order. It allows for easy fetching of the next page with `.next_page()`.
 /// It should never fetch the same transaction twice. It's designed to be able to handle
 /// the case where the pagination catches up with the current state version. In that case,
 /// the amount of items in the response will simply be 0.
+///
+/// The async stream also implements `futures::Stream` and the blocking one `Iterator`,
+/// yielding one transaction at a time and waiting `idle_poll_interval` before fetching
+/// again when caught up. Errors are yielded as items, after which the same page is
+/// fetched again, with an exponential backoff that starts at `idle_poll_interval`,
+/// see [`error_backoff`]. Do not mix this with calls to `.next_page()` on the same
+/// stream.
+///
+/// With a [`CheckpointStore`], the progress survives restarts, see
+/// [`checkpoint`](crate::checkpoint) for the delivery guarantees.
+///
+/// The async stream can [`prefetch`](TransactionStreamAsync::prefetch) pages
+/// while it is behind the ledger tip, see [`prefetch`](super::prefetch).
+///
+/// Without filters, the stream can verify that no state version is missing,
+/// see [`integrity`](super::integrity).
 #[duplicate_item(
-    stream_type                     builder_type ;
-    [ TransactionStreamAsync ]      [ RequestBuilderAsync ];
-    [ TransactionStreamBlocking ]   [ RequestBuilderBlocking ];
+    stream_type                     builder_type                pipeline_type ;
+    [ TransactionStreamAsync ]      [ RequestBuilderAsync ]     [ Pipeline ];
+    [ TransactionStreamBlocking ]   [ RequestBuilderBlocking ]  [ () ];
 )]
 #[derive(Debug)]
 pub struct stream_type {
     pub cursor: Option<String>,
     pub builder: builder_type<TransactionStreamRequestBody>,
     pub last_seen_state_version: u64,
+    pub idle_poll_interval: Duration,
+    buffer: VecDeque<CommittedTransactionInfo>,
+    caught_up: bool,
+    checkpoint: Option<Arc<dyn CheckpointStore>>,
+    checkpoint_mode: CheckpointMode,
+    checkpointed_state_version: u64,
+    integrity: IntegrityMode,
+    counters: StreamCounters,
+    refetch_attempts: u32,
+    /// Failed polls in a row, when used as a `Stream` or `Iterator`.
+    consecutive_errors: u32,
+    // The blocking stream fetches pages in place.
+    pipeline: pipeline_type,
 }
 
 #[duplicate_item(
-    stream_type                         client_type           maybe_async_attr;
-    [ TransactionStreamAsync ]         [ GatewayClientAsync ]    [ must_be_async ];
-    [ TransactionStreamBlocking ]      [ GatewayClientBlocking ] [ must_be_sync ];
+    stream_type                         client_type              new_pipeline            maybe_async_attr ;
+    [ TransactionStreamAsync ]         [ GatewayClientAsync ]    [ Pipeline::default() ] [ must_be_async ];
+    [ TransactionStreamBlocking ]      [ GatewayClientBlocking ] [ () ]                  [ must_be_sync ];
 )]
 impl stream_type {
+    /// Starts at `from_state_version`, which must be at least 1, with
+    /// pages of up to `limit_per_page` transactions, which must be
+    /// between 1 and [`MAX_LIMIT_PER_PAGE`].
     pub fn new(
         client: &client_type,
         from_state_version: u64,
         limit_per_page: u32,
-    ) -> stream_type {
-        if from_state_version == 0 {
-            panic!("from_state_version must be greater than 0");
-        }
+    ) -> Result<stream_type, StreamConfigError> {
+        validate(from_state_version, limit_per_page, MAX_LIMIT_PER_PAGE)?;
         let builder = client
             .transactions_stream_builder()
             .from_state_version(from_state_version)
@@ -46,36 +92,346 @@ impl stream_type {
             .limit_per_page(limit_per_page)
             .with_receipt_events()
             .clone();
-        stream_type {
+        Ok(stream_type {
             cursor: None,
             builder,
             last_seen_state_version: from_state_version - 1,
-        }
+            idle_poll_interval: Duration::from_secs(1),
+            buffer: VecDeque::new(),
+            caught_up: false,
+            checkpoint: None,
+            checkpoint_mode: CheckpointMode::default(),
+            checkpointed_state_version: from_state_version - 1,
+            integrity: IntegrityMode::default(),
+            counters: StreamCounters::default(),
+            refetch_attempts: 0,
+            consecutive_errors: 0,
+            pipeline: new_pipeline,
+        })
     }
 
+    /// Starts after the ledger tip reported by `gateway_status`,
+    /// to follow new transactions only. The first page is requested from
+    /// the tip itself, never past the ledger, and the tip transaction is
+    /// filtered out.
     #[maybe_async_attr]
-    pub async fn next(
+    pub async fn from_ledger_tip(
+        client: &client_type,
+        limit_per_page: u32,
+    ) -> Result<stream_type, GatewayApiError> {
+        let tip = client.gateway_status().await?.ledger_state.state_version;
+        let mut stream = stream_type::new(client, tip, limit_per_page)?;
+        stream.last_seen_state_version = tip;
+        stream.checkpointed_state_version = tip;
+        Ok(stream)
+    }
+
+    /// Continues after the state version in the checkpoint store, or starts
+    /// at `from_state_version` when the store is empty. The stream keeps
+    /// storing its progress in the store.
+    pub fn resume(
+        client: &client_type,
+        store: Arc<dyn CheckpointStore>,
+        from_state_version: u64,
+        limit_per_page: u32,
+    ) -> Result<stream_type, GatewayApiError> {
+        let stored = store.load().map_err(GatewayApiError::Checkpoint)?;
+        let from_state_version = stored
+            .map(|state_version| state_version + 1)
+            .unwrap_or(from_state_version);
+        Ok(
+            stream_type::new(client, from_state_version, limit_per_page)?
+                .with_checkpoint(store),
+        )
+    }
+
+    /// Stores the progress of the stream in the checkpoint store.
+    pub fn with_checkpoint(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint = Some(store);
+        self
+    }
+
+    /// When the progress is stored. Defaults to `AfterPage`.
+    pub fn checkpoint_mode(mut self, value: CheckpointMode) -> Self {
+        self.checkpoint_mode = value;
+        self
+    }
+
+    /// Stores the state version of a transaction that was handled
+    /// in the checkpoint store, if the stream has one.
+    pub fn ack(&mut self, state_version: u64) -> Result<(), GatewayApiError> {
+        if let Some(store) = &self.checkpoint {
+            store
+                .save(state_version)
+                .map_err(GatewayApiError::Checkpoint)?;
+            self.checkpointed_state_version = state_version;
+        }
+        Ok(())
+    }
+
+    /// Stores the progress before the next page is fetched,
+    /// when the stream checkpoints after every page.
+    fn store_progress(&mut self) -> Result<(), GatewayApiError> {
+        if self.checkpoint_mode == CheckpointMode::AfterPage
+            && self.last_seen_state_version > self.checkpointed_state_version
+        {
+            self.ack(self.last_seen_state_version)?;
+        }
+        Ok(())
+    }
+
+    /// Only streams transactions with matching events. The filter is sent
+    /// with every page, as is any other filter set on `builder`.
+    pub fn events_filter(
+        mut self,
+        value: Vec<StreamTransactionsRequestEventFilterItem>,
+    ) -> Self {
+        self.builder.events_filter(value);
+        self
+    }
+
+    pub fn event_global_emitters_filter(mut self, value: Vec<String>) -> Self {
+        self.builder.event_global_emitters_filter(value);
+        self
+    }
+
+    /// How long to wait before fetching again when caught up with
+    /// the ledger tip, when used as a `Stream` or `Iterator`.
+    /// Defaults to one second.
+    pub fn idle_poll_interval(mut self, value: Duration) -> Self {
+        self.idle_poll_interval = value;
+        self
+    }
+
+    /// Verifies that no state version is missing or out of order while
+    /// no filter is set. Defaults to `Off`. Set the kind filter to `All`
+    /// on `builder` to stream every state version.
+    pub fn integrity(mut self, value: IntegrityMode) -> Self {
+        self.integrity = value;
+        self
+    }
+
+    pub fn counters(&self) -> &StreamCounters {
+        &self.counters
+    }
+
+    /// Drops the transactions that were already seen and moves the
+    /// stream past the remaining ones, or past the end of the range
+    /// for a prefetched page. A page that fails the integrity check
+    /// leaves the stream where it was.
+    fn advance(
         &mut self,
-    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
-        let mut response = self.builder.fetch().await?;
+        mut response: TransactionStream200ResponseBody,
+        range_end: Option<u64>,
+    ) -> Result<TransactionStream200ResponseBody, IntegrityError> {
+        if self.integrity != IntegrityMode::Off
+            && is_unfiltered(&self.builder.request)
+        {
+            verify(&response.items, self.last_seen_state_version, range_end)?;
+        }
+        self.refetch_attempts = 0;
 
-        response.items = response
+        let count = response.items.len();
+        response
             .items
-            .into_iter()
-            .filter(|item| item.state_version > self.last_seen_state_version)
-            .collect();
+            .retain(|item| item.state_version > self.last_seen_state_version);
+        self.counters.duplicates_filtered +=
+            (count - response.items.len()) as u64;
 
-        let last = response.items.last();
-        if let Some(transaction) = last {
-            self.builder.from_state_version(
-                (transaction.state_version + 1)
-                    .min(response.ledger_state.state_version),
-            );
-            self.last_seen_state_version = transaction.state_version;
+        let last = response
+            .items
+            .last()
+            .map(|transaction| transaction.state_version)
+            .max(range_end);
+        if let Some(state_version) = last {
+            // Ranges end below the ledger tip. Otherwise the next page
+            // starts at the tip at most, the gateway rejects later ones.
+            let next = match range_end {
+                Some(range_end) => range_end + 1,
+                None => {
+                    (state_version + 1).min(response.ledger_state.state_version)
+                }
+            };
+            self.builder.from_state_version(next);
+            self.last_seen_state_version = state_version;
         }
+        self.caught_up = range_end.is_none() && response.items.is_empty();
 
         Ok(response)
     }
+
+    /// Counts a page that failed the integrity check. Returns the error
+    /// unless the page should be fetched again.
+    fn on_violation(
+        &mut self,
+        error: IntegrityError,
+    ) -> Result<(), GatewayApiError> {
+        self.counters.integrity_violations += 1;
+        if self.integrity == IntegrityMode::Refetch
+            && self.refetch_attempts < MAX_REFETCHES
+        {
+            self.refetch_attempts += 1;
+            self.counters.refetches += 1;
+            return Ok(());
+        }
+        self.refetch_attempts = 0;
+        Err(GatewayApiError::StreamIntegrity(error))
+    }
+}
+
+impl TransactionStreamAsync {
+    /// Keeps up to `depth` page requests in flight while the stream is
+    /// behind the ledger tip. Pages are still delivered in order.
+    /// Defaults to 0, which fetches one page at a time.
+    pub fn prefetch(mut self, depth: usize) -> Self {
+        self.pipeline.depth = depth;
+        self
+    }
+
+    pub async fn next_page(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
+        self.store_progress()?;
+        std::future::poll_fn(|cx| self.poll_page(cx, None)).await
+    }
+
+    /// The old name of [`Self::next_page`]. Being an inherent method, it
+    /// takes precedence over `StreamExt::next`.
+    #[deprecated(note = "use `next_page`, `next` hides `StreamExt::next`")]
+    pub async fn next(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
+        self.next_page().await
+    }
+
+    /// Polls for the next page. A page that is requested
+    /// without prefetching is requested after `delay`.
+    fn poll_page(
+        &mut self,
+        cx: &mut Context<'_>,
+        delay: Option<Duration>,
+    ) -> Poll<Result<TransactionStream200ResponseBody, GatewayApiError>> {
+        loop {
+            self.pipeline.fill(
+                &self.builder,
+                self.last_seen_state_version + 1,
+                delay,
+            );
+            let (range_end, result) = match self.pipeline.poll(cx) {
+                Poll::Ready(page) => page,
+                Poll::Pending => return Poll::Pending,
+            };
+            let response = match result {
+                Ok(response) => response,
+                Err(error) => return Poll::Ready(Err(error)),
+            };
+            match self.advance(response, range_end) {
+                Ok(page) => return Poll::Ready(Ok(page)),
+                Err(error) => {
+                    // Later ranges were requested from the same position.
+                    self.pipeline.clear();
+                    if let Err(error) = self.on_violation(error) {
+                        return Poll::Ready(Err(error));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TransactionStreamBlocking {
+    pub fn next_page(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
+        self.store_progress()?;
+        self.fetch_page()
+    }
+
+    /// The old name of [`Self::next_page`]. Being an inherent method, it
+    /// takes precedence over `Iterator::next`.
+    #[deprecated(note = "use `next_page`, `next` hides `Iterator::next`")]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
+        self.next_page()
+    }
+
+    fn fetch_page(
+        &mut self,
+    ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
+        loop {
+            let response = self.builder.fetch()?;
+            match self.advance(response, None) {
+                Ok(page) => return Ok(page),
+                Err(error) => self.on_violation(error)?,
+            }
+        }
+    }
+}
+
+impl Stream for TransactionStreamAsync {
+    type Item = Result<CommittedTransactionInfo, GatewayApiError>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(transaction) = this.buffer.pop_front() {
+                return Poll::Ready(Some(Ok(transaction)));
+            }
+            if let Err(error) = this.store_progress() {
+                return Poll::Ready(Some(Err(error)));
+            }
+            let delay =
+                error_backoff(this.idle_poll_interval, this.consecutive_errors)
+                    .or(this.caught_up.then_some(this.idle_poll_interval));
+            match this.poll_page(cx, delay) {
+                Poll::Ready(Ok(page)) => {
+                    this.consecutive_errors = 0;
+                    this.buffer.extend(page.items)
+                }
+                Poll::Ready(Err(error)) => {
+                    this.consecutive_errors += 1;
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl Iterator for TransactionStreamBlocking {
+    type Item = Result<CommittedTransactionInfo, GatewayApiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(transaction) = self.buffer.pop_front() {
+                return Some(Ok(transaction));
+            }
+            if let Err(error) = self.store_progress() {
+                return Some(Err(error));
+            }
+            let delay =
+                error_backoff(self.idle_poll_interval, self.consecutive_errors)
+                    .or(self.caught_up.then_some(self.idle_poll_interval));
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            match self.fetch_page() {
+                Ok(page) => {
+                    self.consecutive_errors = 0;
+                    self.buffer.extend(page.items)
+                }
+                Err(error) => {
+                    self.consecutive_errors += 1;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
 }
 
 #[duplicate_item(
@@ -88,27 +444,483 @@ impl client_type {
         &self,
         from_state_version: u64,
         limit_per_page: u32,
-    ) -> stream_type {
-        stream_type::new(&self, from_state_version, limit_per_page)
+    ) -> Result<stream_type, StreamConfigError> {
+        stream_type::new(self, from_state_version, limit_per_page)
+    }
+
+    /// A transaction stream of the transactions
+    /// committed after the current ledger tip.
+    #[maybe_async_attr]
+    pub async fn transaction_stream_from_ledger_tip(
+        &self,
+        limit_per_page: u32,
+    ) -> Result<stream_type, GatewayApiError> {
+        stream_type::from_ledger_tip(self, limit_per_page).await
+    }
+
+    /// A transaction stream that continues where the last
+    /// one with the same checkpoint store stopped.
+    pub fn resume_transaction_stream(
+        &self,
+        store: Arc<dyn CheckpointStore>,
+        from_state_version: u64,
+        limit_per_page: u32,
+    ) -> Result<stream_type, GatewayApiError> {
+        stream_type::resume(self, store, from_state_version, limit_per_page)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::checkpoint::InMemoryCheckpointStore;
+    use crate::gateway::stream::test_utils::transaction_page;
+    use crate::transport::MockTransport;
+    use crate::GatewayClientBuilder;
+    use reqwest::StatusCode;
+    use std::rc::Rc;
+    use std::sync::Arc;
     use std::{thread::sleep, time::Duration};
 
+    #[test]
+    fn next_skips_seen_transactions_and_advances() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        // The gateway may return the last seen transaction again.
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[6, 7]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut stream = client.new_transaction_stream(5, 2).unwrap();
+
+        let versions = |page: TransactionStream200ResponseBody| {
+            page.items
+                .iter()
+                .map(|item| item.state_version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(versions(stream.next_page().unwrap()), vec![5, 6]);
+        assert_eq!(versions(stream.next_page().unwrap()), vec![7]);
+        assert!(versions(stream.next_page().unwrap()).is_empty());
+        assert_eq!(stream.last_seen_state_version, 7);
+
+        let requests = mock.requests();
+        let from_state_version = |index: usize| {
+            let body: serde_json::Value =
+                serde_json::from_str(&requests[index].body).unwrap();
+            body["from_ledger_state"]["state_version"].as_u64().unwrap()
+        };
+        assert_eq!(from_state_version(0), 5);
+        assert_eq!(from_state_version(1), 7);
+        assert_eq!(from_state_version(2), 8);
+    }
+
+    #[tokio::test]
+    async fn async_next_does_not_pass_the_ledger_tip() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(3, &[2, 3]));
+        mock.respond_ok("stream/transactions", &transaction_page(3, &[3]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let mut stream = client.new_transaction_stream(2, 10).unwrap();
+
+        assert_eq!(stream.next_page().await.unwrap().items.len(), 2);
+        // Caught up: the same transaction is returned again and filtered.
+        assert!(stream.next_page().await.unwrap().items.is_empty());
+        assert_eq!(stream.last_seen_state_version, 3);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn next_is_kept_as_an_alias_of_next_page() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .build_blocking()
+            .unwrap();
+        let mut stream = client.new_transaction_stream(5, 2).unwrap();
+
+        assert_eq!(stream.next().unwrap().items.len(), 2);
+        assert_eq!(stream.last_seen_state_version, 6);
+    }
+
+    #[test]
+    fn filters_are_sent_with_every_page() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[9]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream(5, 10)
+            .unwrap()
+            .events_filter(vec![StreamTransactionsRequestEventFilterItem {
+                event: StreamTransactionsRequestEventFilterItemEvent::Deposit,
+                emitter_address: Some("account_rdx1".to_string()),
+                resource_address: None,
+            }])
+            .event_global_emitters_filter(vec!["component_rdx1".to_string()]);
+        stream.next_page().unwrap();
+        stream.next_page().unwrap();
+
+        for request in mock.requests() {
+            let body: serde_json::Value =
+                serde_json::from_str(&request.body).unwrap();
+            assert_eq!(body["events_filter"][0]["event"], "Deposit");
+            assert_eq!(
+                body["events_filter"][0]["emitter_address"],
+                "account_rdx1"
+            );
+            assert_eq!(
+                body["event_global_emitters_filter"][0],
+                "component_rdx1"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_yields_transactions_and_polls_when_caught_up() {
+        use futures::StreamExt;
+
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[]));
+        mock.respond("stream/transactions", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[7]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let stream = client
+            .new_transaction_stream(5, 2)
+            .unwrap()
+            .idle_poll_interval(Duration::from_millis(1));
+
+        let items: Vec<_> = stream.take(4).collect().await;
+        let versions: Vec<_> = items
+            .iter()
+            .filter_map(|item| item.as_ref().ok())
+            .map(|transaction| transaction.state_version)
+            .collect();
+        assert_eq!(versions, vec![5, 6, 7]);
+        assert!(items[2].is_err());
+        assert_eq!(mock.requests().len(), 4);
+    }
+
+    #[test]
+    fn iterator_yields_transactions() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[6, 7]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let stream = client
+            .new_transaction_stream(5, 2)
+            .unwrap()
+            .idle_poll_interval(Duration::from_millis(1));
+
+        let versions: Vec<_> = stream
+            .take(3)
+            .map(|item| item.unwrap().state_version)
+            .collect();
+        assert_eq!(versions, vec![5, 6, 7]);
+    }
+
+    #[tokio::test]
+    async fn stream_backs_off_after_errors() {
+        use futures::StreamExt;
+
+        let mock = Arc::new(MockTransport::new());
+        mock.respond("stream/transactions", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock)
+            .build_async()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream(5, 2)
+            .unwrap()
+            .idle_poll_interval(Duration::from_millis(50));
+
+        assert!(StreamExt::next(&mut stream).await.unwrap().is_err());
+        let started = std::time::Instant::now();
+        assert_eq!(
+            StreamExt::next(&mut stream)
+                .await
+                .unwrap()
+                .unwrap()
+                .state_version,
+            5
+        );
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn resumes_after_the_checkpoint() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[7]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[7, 8]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let store = Arc::new(InMemoryCheckpointStore::new());
+
+        let mut stream = client
+            .resume_transaction_stream(store.clone(), 5, 2)
+            .unwrap();
+        stream.next_page().unwrap();
+        // Not stored until the page is handled and the next one requested.
+        assert_eq!(store.load().unwrap(), None);
+        stream.next_page().unwrap();
+        assert_eq!(store.load().unwrap(), Some(6));
+
+        // Page 7 was not handled before the restart, so it is delivered again.
+        let mut stream = client
+            .resume_transaction_stream(store.clone(), 5, 2)
+            .unwrap();
+        let transaction = Iterator::next(&mut stream).unwrap().unwrap();
+        assert_eq!(transaction.state_version, 7);
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[2].body).unwrap();
+        assert_eq!(body["from_ledger_state"]["state_version"], 7);
+    }
+
+    #[test]
+    fn stores_only_acknowledged_state_versions() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[7]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .build_blocking()
+            .unwrap();
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let mut stream = client
+            .resume_transaction_stream(store.clone(), 5, 2)
+            .unwrap()
+            .checkpoint_mode(CheckpointMode::OnAck);
+
+        let first = Iterator::next(&mut stream).unwrap().unwrap();
+        stream.ack(first.state_version).unwrap();
+        Iterator::next(&mut stream).unwrap().unwrap();
+        Iterator::next(&mut stream).unwrap().unwrap();
+        assert_eq!(store.load().unwrap(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn prefetches_ranges_until_the_ledger_tip() {
+        let mock = Arc::new(MockTransport::new());
+        let pages = [
+            transaction_page(10, &[1, 2]),
+            transaction_page(4, &[3, 4]),
+            // Transactions filtered out leave gaps in a range.
+            transaction_page(6, &[6]),
+            transaction_page(8, &[]),
+            transaction_page(12, &[9, 10]),
+            transaction_page(12, &[11, 12]),
+        ];
+        for page in &pages {
+            mock.respond_ok("stream/transactions", page);
+        }
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let mut stream =
+            client.new_transaction_stream(1, 2).unwrap().prefetch(3);
+
+        let mut versions = vec![];
+        for _ in 0..pages.len() {
+            let page = stream.next_page().await.unwrap();
+            versions.extend(page.items.iter().map(|item| item.state_version));
+            if versions == [1, 2, 3, 4] {
+                // The first page and three ranges were sent.
+                assert_eq!(mock.requests().len(), 4);
+            }
+        }
+        assert_eq!(versions, vec![1, 2, 3, 4, 6, 9, 10, 11, 12]);
+
+        let ranges: Vec<_> = mock
+            .requests()
+            .iter()
+            .map(|request| {
+                let body: serde_json::Value =
+                    serde_json::from_str(&request.body).unwrap();
+                (
+                    body["from_ledger_state"]["state_version"].as_u64(),
+                    body["at_ledger_state"]["state_version"].as_u64(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (Some(1), None),
+                (Some(3), Some(4)),
+                (Some(5), Some(6)),
+                (Some(7), Some(8)),
+                // A range would reach the ledger tip of 10.
+                (Some(9), None),
+                (Some(11), None),
+            ]
+        );
+        assert_eq!(stream.counters().duplicates_filtered, 0);
+    }
+
+    #[test]
+    fn reports_gaps_and_counts_duplicates() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[5, 6]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[6, 8]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[6, 7]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock.clone())
+            .build_blocking()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream(5, 2)
+            .unwrap()
+            .integrity(IntegrityMode::Error);
+        stream.builder.kind_filter(TransactionKindFilter::All);
+
+        stream.next_page().unwrap();
+        match stream.next_page() {
+            Err(GatewayApiError::StreamIntegrity(error)) => {
+                assert_eq!(error, IntegrityError::Gap { after: 6, next: 8 })
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(stream.last_seen_state_version, 6);
+        assert_eq!(stream.next_page().unwrap().items.len(), 1);
+        assert_eq!(
+            *stream.counters(),
+            StreamCounters {
+                duplicates_filtered: 1,
+                integrity_violations: 1,
+                refetches: 0,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn refetches_pages_with_gaps() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[1, 3]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[1, 2]));
+        mock.respond_ok("stream/transactions", &transaction_page(20, &[2, 1]));
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let mut stream = client
+            .new_transaction_stream(1, 2)
+            .unwrap()
+            .integrity(IntegrityMode::Refetch);
+        stream.builder.kind_filter(TransactionKindFilter::All);
+
+        let page = stream.next_page().await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        assert_eq!(stream.counters().refetches, 1);
+        assert_eq!(mock.requests().len(), 2);
+
+        // Filtered streams are not verified.
+        stream.builder.kind_filter(TransactionKindFilter::User);
+        assert!(stream.next_page().await.is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let client = GatewayClientBlocking::new("http://mock".to_string());
+        assert_eq!(
+            client.new_transaction_stream(0, 10).unwrap_err(),
+            StreamConfigError::ZeroFromStateVersion
+        );
+        assert_eq!(
+            client.new_transaction_stream(1, 0).unwrap_err(),
+            StreamConfigError::ZeroLimitPerPage
+        );
+        assert_eq!(
+            client.new_transaction_stream(1, 101).unwrap_err(),
+            StreamConfigError::LimitPerPageTooLarge {
+                limit: 101,
+                max: MAX_LIMIT_PER_PAGE
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn starts_after_the_ledger_tip() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok(
+            "status/gateway-status",
+            include_str!(
+                "../../../tests/fixtures/gateway/status_gateway_status.json"
+            ),
+        );
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock)
+            .build_async()
+            .unwrap();
+        let stream = client
+            .transaction_stream_from_ledger_tip(100)
+            .await
+            .unwrap();
+        assert_eq!(stream.last_seen_state_version, 100246389);
+    }
+
+    #[tokio::test]
+    async fn skips_the_ledger_tip_transaction() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok(
+            "status/gateway-status",
+            include_str!(
+                "../../../tests/fixtures/gateway/status_gateway_status.json"
+            ),
+        );
+        mock.respond_ok(
+            "stream/transactions",
+            &transaction_page(100246390, &[100246389, 100246390]),
+        );
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(mock.clone())
+            .build_async()
+            .unwrap();
+        let mut stream = client
+            .transaction_stream_from_ledger_tip(100)
+            .await
+            .unwrap();
+
+        let page = stream.next_page().await.unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].state_version, 100246390);
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[1].body).unwrap();
+        assert_eq!(body["from_ledger_state"]["state_version"], 100246389);
+    }
+
     #[test]
     fn test_30_transactions() {
         let client = GatewayClientBlocking::new(
             "https://mainnet.radixdlt.com".to_string(),
         );
-        let mut stream = client.new_transaction_stream(1, 3);
+        let mut stream = client.new_transaction_stream(1, 3).unwrap();
 
         let mut count = 0;
         for _ in 0..10 {
-            let response = stream.next().unwrap();
-            if response.items.len() == 0 {
+            let response = stream.next_page().unwrap();
+            if response.items.is_empty() {
                 sleep(Duration::from_secs(1));
                 continue;
             }
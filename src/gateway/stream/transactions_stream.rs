@@ -21,7 +21,7 @@ impl client_type {
         request: TransactionStreamRequestBody,
     ) -> Result<TransactionStream200ResponseBody, GatewayApiError> {
         let (text, status) = self.post("stream/transactions", request).await?;
-        match_response(text, status)
+        self.observe("stream/transactions", match_response(text, status))
     }
 }
 
@@ -84,6 +84,99 @@ impl builder_type<TransactionStreamRequestBody> {
         self
     }
 
+    pub fn manifest_badges_presented_filter(
+        &mut self,
+        value: Vec<String>,
+    ) -> &mut Self {
+        self.request.manifest_badges_presented_filter = Some(value);
+        self
+    }
+
+    /// Only transactions that call an owner-protected method,
+    /// and so need the authorization, of all of the accounts.
+    pub fn accounts_with_manifest_owner_method_calls(
+        &mut self,
+        value: Vec<String>,
+    ) -> &mut Self {
+        self.request.accounts_with_manifest_owner_method_calls = Some(value);
+        self
+    }
+
+    pub fn accounts_without_manifest_owner_method_calls(
+        &mut self,
+        value: Vec<String>,
+    ) -> &mut Self {
+        self.request.accounts_without_manifest_owner_method_calls = Some(value);
+        self
+    }
+
+    pub fn manifest_class_filter(
+        &mut self,
+        class: ManifestClass,
+        match_only_most_specific: bool,
+    ) -> &mut Self {
+        self.request.manifest_class_filter = Some(ManifestClassFilter {
+            class,
+            match_only_most_specific,
+        });
+        self
+    }
+
+    pub fn events_filter(
+        &mut self,
+        value: Vec<StreamTransactionsRequestEventFilterItem>,
+    ) -> &mut Self {
+        self.request.events_filter = Some(value);
+        self
+    }
+
+    /// Adds a filter for deposit events, optionally limited
+    /// to an emitter address and a resource address.
+    pub fn deposit_events_filter(
+        &mut self,
+        emitter_address: Option<String>,
+        resource_address: Option<String>,
+    ) -> &mut Self {
+        self.add_event_filter(StreamTransactionsRequestEventFilterItem {
+            event: StreamTransactionsRequestEventFilterItemEvent::Deposit,
+            emitter_address,
+            resource_address,
+        })
+    }
+
+    /// Adds a filter for withdrawal events, optionally limited
+    /// to an emitter address and a resource address.
+    pub fn withdrawal_events_filter(
+        &mut self,
+        emitter_address: Option<String>,
+        resource_address: Option<String>,
+    ) -> &mut Self {
+        self.add_event_filter(StreamTransactionsRequestEventFilterItem {
+            event: StreamTransactionsRequestEventFilterItemEvent::Withdrawal,
+            emitter_address,
+            resource_address,
+        })
+    }
+
+    fn add_event_filter(
+        &mut self,
+        value: StreamTransactionsRequestEventFilterItem,
+    ) -> &mut Self {
+        self.request
+            .events_filter
+            .get_or_insert_with(Vec::new)
+            .push(value);
+        self
+    }
+
+    pub fn event_global_emitters_filter(
+        &mut self,
+        value: Vec<String>,
+    ) -> &mut Self {
+        self.request.event_global_emitters_filter = Some(value);
+        self
+    }
+
     // pub struct LedgerStateSelector {
     //     pub state_version: Option<u64>,
     //     pub timestamp: Option<u64>,
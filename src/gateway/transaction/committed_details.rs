@@ -0,0 +1,235 @@
+use self::gateway::{
+    builder::{RequestBuilderAsync, RequestBuilderBlocking},
+    error::GatewayApiError,
+    match_response,
+    models::*,
+};
+use crate::*;
+use chrono::Utc;
+use duplicate::duplicate_item;
+use maybe_async::*;
+
+#[duplicate_item(
+    client_type                 maybe_async_attr ;
+    [ GatewayClientAsync ]     [ must_be_async ];
+    [ GatewayClientBlocking ]  [ must_be_sync ];
+  )]
+impl client_type {
+    #[maybe_async_attr]
+    pub async fn transaction_committed_details(
+        &self,
+        request: TransactionCommittedDetailsRequestBody,
+    ) -> Result<TransactionCommittedDetails200ResponseBody, GatewayApiError>
+    {
+        let (text, status) =
+            self.post("transaction/committed-details", request).await?;
+        self.observe(
+            "transaction/committed-details",
+            match_response(text, status),
+        )
+    }
+}
+
+// builder
+
+#[duplicate_item(
+    request_type client_type ;
+    [ RequestBuilderAsync ] [ GatewayClientAsync ] ;
+    [ RequestBuilderBlocking ] [ GatewayClientBlocking ] ;
+)]
+impl client_type {
+    pub fn transaction_committed_details_builder(
+        &self,
+        intent_hash: String,
+    ) -> request_type<TransactionCommittedDetailsRequestBody> {
+        let request = TransactionCommittedDetailsRequestBody {
+            intent_hash,
+            ..Default::default()
+        };
+        request_type {
+            client: self.clone(),
+            request,
+        }
+    }
+}
+
+#[duplicate_item(
+    builder_type maybe_async_attr;
+    [ RequestBuilderAsync ] [ must_be_async ];
+    [ RequestBuilderBlocking ] [ must_be_sync ];
+)]
+impl builder_type<TransactionCommittedDetailsRequestBody> {
+    pub fn at_state_version(&mut self, value: u64) -> &mut Self {
+        self.request.at_ledger_state = Some(LedgerStateSelector {
+            state_version: Some(value),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn at_timestamp(&mut self, value: chrono::DateTime<Utc>) -> &mut Self {
+        self.request.at_ledger_state = Some(LedgerStateSelector {
+            timestamp: Some(value.timestamp() as u64),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn at_epoch(&mut self, value: u64) -> &mut Self {
+        self.request.at_ledger_state = Some(LedgerStateSelector {
+            epoch: Some(value),
+            ..Default::default()
+        });
+        self
+    }
+
+    pub fn at_round(&mut self, value: u64) -> &mut Self {
+        self.request.at_ledger_state = Some(LedgerStateSelector {
+            round: Some(value),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Helper function to update the opt_ins field
+    /// with less boilerplate code in each setter.
+    fn update_opt_ins(
+        &mut self,
+        function: fn(TransactionDetailsOptIns) -> TransactionDetailsOptIns,
+    ) -> &mut Self {
+        self.request.opt_ins = self
+            .request
+            .opt_ins
+            .clone()
+            .or(Some(TransactionDetailsOptIns::default()))
+            .map(function);
+        self
+    }
+
+    pub fn with_raw_hex(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.raw_hex = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_state_changes(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_state_changes = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_fee_summary(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_fee_summary = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_fee_source(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_fee_source = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_fee_destination(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_fee_destination = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_costing_parameters(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_costing_parameters = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_events(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_events = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_receipt_output(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.receipt_output = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_affected_global_entities(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.affected_global_entities = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_manifest_instructions(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.manifest_instructions = true;
+            opt_ins
+        })
+    }
+
+    pub fn with_balance_changes(&mut self) -> &mut Self {
+        self.update_opt_ins(|mut opt_ins| {
+            opt_ins.balance_changes = true;
+            opt_ins
+        })
+    }
+
+    #[maybe_async_attr]
+    pub async fn fetch(
+        &self,
+    ) -> Result<TransactionCommittedDetails200ResponseBody, GatewayApiError>
+    {
+        self.client
+            .transaction_committed_details(self.request.clone())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::{MockTransport, TransportBlocking};
+    use crate::GatewayClientBuilder;
+    use std::rc::Rc;
+
+    #[test]
+    fn builder_sends_the_opt_ins() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok(
+            "transaction/committed-details",
+            include_str!(
+                "../../../tests/fixtures/gateway/transaction_committed_details.json"
+            ),
+        );
+        let transport: Rc<dyn TransportBlocking> = mock.clone();
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(transport)
+            .build_blocking()
+            .unwrap();
+
+        let details = client
+            .transaction_committed_details_builder("txid_rdx1".to_string())
+            .at_state_version(100246389)
+            .with_raw_hex()
+            .with_receipt_events()
+            .fetch()
+            .unwrap();
+        assert_eq!(details.transaction.state_version, 100246366);
+
+        let body: serde_json::Value =
+            serde_json::from_str(&mock.requests()[0].body).unwrap();
+        assert_eq!(body["intent_hash"], "txid_rdx1");
+        assert_eq!(body["at_ledger_state"]["state_version"], 100246389);
+        assert_eq!(body["opt_ins"]["raw_hex"], true);
+        assert_eq!(body["opt_ins"]["receipt_events"], true);
+        assert_eq!(body["opt_ins"]["balance_changes"], false);
+    }
+}
@@ -1,2 +1,4 @@
+pub mod committed_details;
 pub mod preview_transaction;
 pub mod submit_transaction;
+pub mod transaction_status;
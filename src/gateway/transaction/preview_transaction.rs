@@ -20,7 +20,7 @@ impl client_type {
         request: TransactionPreviewRequestBody,
     ) -> Result<TransactionPreview200ResponseBody, GatewayApiError> {
         let (text, status) = self.post("transaction/preview", request).await?;
-        match_response(text, status)
+        self.observe("transaction/preview", match_response(text, status))
     }
 }
 
@@ -18,6 +18,6 @@ impl client_type {
             notarized_transaction_hex,
         };
         let (text, status) = self.post("transaction/submit", body).await?;
-        match_response(text, status)
+        self.observe("transaction/submit", match_response(text, status))
     }
 }
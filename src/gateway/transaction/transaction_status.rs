@@ -0,0 +1,238 @@
+use self::gateway::{error::GatewayApiError, match_response, models::*};
+use crate::*;
+use duplicate::duplicate_item;
+use maybe_async::*;
+use std::time::{Duration, Instant};
+
+/// How [`submit_and_wait`](GatewayClientAsync::submit_and_wait) and
+/// [`wait_for_transaction`](GatewayClientAsync::wait_for_transaction)
+/// poll the status of a transaction.
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    pub poll_interval: Duration,
+    /// How long to wait for an outcome before giving up.
+    pub timeout: Duration,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        WaitOptions {
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn poll_interval(mut self, value: Duration) -> Self {
+        self.poll_interval = value;
+        self
+    }
+
+    pub fn timeout(mut self, value: Duration) -> Self {
+        self.timeout = value;
+        self
+    }
+}
+
+/// The outcome of waiting for a transaction, with the last status
+/// that was received.
+#[derive(Debug, Clone)]
+pub enum TransactionOutcome {
+    CommittedSuccess(TransactionStatus200ResponseBody),
+    CommittedFailure(TransactionStatus200ResponseBody),
+    PermanentlyRejected(TransactionStatus200ResponseBody),
+    /// The ledger passed `permanently_rejects_at_epoch`
+    /// without the transaction being committed.
+    Expired(TransactionStatus200ResponseBody),
+    /// The transaction was still pending when the timeout passed.
+    TimedOut(TransactionStatus200ResponseBody),
+}
+
+impl TransactionOutcome {
+    /// Returns the status back when the transaction is still pending.
+    pub fn from_status(
+        status: TransactionStatus200ResponseBody,
+    ) -> Result<Self, Box<TransactionStatus200ResponseBody>> {
+        let expired = status
+            .permanently_rejects_at_epoch
+            .is_some_and(|epoch| status.ledger_state.epoch >= epoch);
+        match status.intent_status {
+            TransactionIntentStatus::CommittedSuccess => {
+                Ok(TransactionOutcome::CommittedSuccess(status))
+            }
+            TransactionIntentStatus::CommittedFailure => {
+                Ok(TransactionOutcome::CommittedFailure(status))
+            }
+            TransactionIntentStatus::PermanentlyRejected => {
+                Ok(TransactionOutcome::PermanentlyRejected(status))
+            }
+            _ if expired => Ok(TransactionOutcome::Expired(status)),
+            _ => Err(Box::new(status)),
+        }
+    }
+
+    pub fn is_committed_success(&self) -> bool {
+        matches!(self, TransactionOutcome::CommittedSuccess(_))
+    }
+
+    pub fn status(&self) -> &TransactionStatus200ResponseBody {
+        match self {
+            TransactionOutcome::CommittedSuccess(status)
+            | TransactionOutcome::CommittedFailure(status)
+            | TransactionOutcome::PermanentlyRejected(status)
+            | TransactionOutcome::Expired(status)
+            | TransactionOutcome::TimedOut(status) => status,
+        }
+    }
+}
+
+#[duplicate_item(
+    client_type                 maybe_async_attr    sleep;
+    [ GatewayClientAsync ]     [ must_be_async ]   [ tokio::time::sleep ];
+    [ GatewayClientBlocking ]  [ must_be_sync ]    [ std::thread::sleep ];
+  )]
+impl client_type {
+    #[maybe_async_attr]
+    pub async fn transaction_status(
+        &self,
+        intent_hash: String,
+    ) -> Result<TransactionStatus200ResponseBody, GatewayApiError> {
+        let body = TransactionStatusRequestBody { intent_hash };
+        let (text, status) = self.post("transaction/status", body).await?;
+        self.observe("transaction/status", match_response(text, status))
+    }
+
+    /// Polls the status of the intent until it is committed, permanently
+    /// rejected, expired, or the timeout passes.
+    #[maybe_async_attr]
+    pub async fn wait_for_transaction(
+        &self,
+        intent_hash: String,
+        options: &WaitOptions,
+    ) -> Result<TransactionOutcome, GatewayApiError> {
+        let started = Instant::now();
+        loop {
+            let status = self.transaction_status(intent_hash.clone()).await?;
+            let status = match TransactionOutcome::from_status(status) {
+                Ok(outcome) => return Ok(outcome),
+                Err(status) => *status,
+            };
+            if started.elapsed() + options.poll_interval > options.timeout {
+                return Ok(TransactionOutcome::TimedOut(status));
+            }
+            sleep(options.poll_interval).await;
+        }
+    }
+
+    /// Submits the transaction and waits for its outcome. The intent hash
+    /// is the one of the notarized transaction, as returned by the
+    /// transaction builder that created it.
+    #[maybe_async_attr]
+    pub async fn submit_and_wait(
+        &self,
+        notarized_transaction_hex: String,
+        intent_hash: String,
+        options: &WaitOptions,
+    ) -> Result<TransactionOutcome, GatewayApiError> {
+        self.submit_transaction(notarized_transaction_hex).await?;
+        self.wait_for_transaction(intent_hash, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TransactionOutcome, WaitOptions};
+    use crate::transport::{MockTransport, TransportAsync, TransportBlocking};
+    use crate::GatewayClientBuilder;
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn status(intent_status: &str, epoch: u64) -> String {
+        serde_json::json!({
+            "ledger_state": {
+                "network": "mainnet",
+                "state_version": 100,
+                "proposer_round_timestamp": "2024-06-12T09:41:27.345Z",
+                "epoch": epoch,
+                "round": 1
+            },
+            "status": "Pending",
+            "intent_status": intent_status,
+            "intent_status_description": "",
+            "known_payloads": [],
+            "permanently_rejects_at_epoch": 10
+        })
+        .to_string()
+    }
+
+    fn options() -> WaitOptions {
+        WaitOptions::default().poll_interval(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn submit_and_wait_polls_until_committed() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("transaction/submit", r#"{"duplicate":false}"#);
+        mock.respond_ok("transaction/status", &status("Pending", 8));
+        mock.respond_ok(
+            "transaction/status",
+            &status("CommitPendingOutcomeUnknown", 8),
+        );
+        mock.respond_ok("transaction/status", &status("CommittedSuccess", 8));
+        let transport: Rc<dyn TransportBlocking> = mock.clone();
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(transport)
+            .build_blocking()
+            .unwrap();
+
+        let outcome = client
+            .submit_and_wait(
+                "4d22".to_string(),
+                "txid_rdx1".to_string(),
+                &options(),
+            )
+            .unwrap();
+        assert!(outcome.is_committed_success());
+        assert_eq!(mock.requests().len(), 4);
+        assert_eq!(mock.requests()[1].body, r#"{"intent_hash":"txid_rdx1"}"#);
+    }
+
+    #[tokio::test]
+    async fn wait_stops_when_the_epoch_window_expires() {
+        let mock = Arc::new(MockTransport::new());
+        mock.respond_ok("transaction/status", &status("Pending", 9));
+        mock.respond_ok("transaction/status", &status("Pending", 10));
+        let transport: Arc<dyn TransportAsync> = mock;
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_async(transport)
+            .build_async()
+            .unwrap();
+
+        let outcome = client
+            .wait_for_transaction("txid_rdx1".to_string(), &options())
+            .await
+            .unwrap();
+        assert!(matches!(outcome, TransactionOutcome::Expired(_)));
+    }
+
+    #[test]
+    fn wait_times_out() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("transaction/status", &status("Pending", 1));
+        let transport: Rc<dyn TransportBlocking> = mock;
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(transport)
+            .build_blocking()
+            .unwrap();
+
+        let outcome = client
+            .wait_for_transaction(
+                "txid_rdx1".to_string(),
+                &options().timeout(Duration::ZERO),
+            )
+            .unwrap();
+        assert!(matches!(outcome, TransactionOutcome::TimedOut(_)));
+    }
+}
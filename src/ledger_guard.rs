@@ -0,0 +1,278 @@
+//! # Ledger guard
+//!
+//! Keeps the responses of a client from going back in time. Gateway
+//! responses carry the `ledger_state` they were served at, Core responses
+//! the `at_ledger_state` of a state read or call preview, or the
+//! `max_ledger_state_version` of the transaction stream. A lagging Gateway
+//! or Core node can serve an older one than a previous response. Core
+//! responses without a state version, such as the mempool, are not
+//! checked.
+//! The guard records the highest `state_version` seen and turns an older
+//! response into a [`TransportError::StaleLedgerState`]. When the client
+//! has [fallbacks](crate::failover), the request is sent to the next
+//! endpoint instead and the lagging one is put in a cool-down.
+//!
+//! Requests with an explicit `at_ledger_state` read a past ledger state on
+//! purpose, e.g. the ranges of a prefetching stream or a backfill, so their
+//! responses are neither checked nor raise the highest state version.
+//!
+//! The guard can also pin reads to the highest state version seen, so a
+//! read that spans several requests sees a single consistent ledger state.
+
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+pub struct LedgerGuard {
+    highest: AtomicU64,
+    pinned: Mutex<Option<u64>>,
+}
+
+#[derive(Deserialize)]
+struct ResponseLedgerState {
+    /// Gateway responses.
+    ledger_state: Option<StateVersion>,
+    /// Core state reads and call previews.
+    at_ledger_state: Option<StateVersion>,
+    /// Core `stream/transactions`.
+    max_ledger_state_version: Option<u64>,
+}
+
+impl ResponseLedgerState {
+    fn state_version(self) -> Option<u64> {
+        self.ledger_state
+            .or(self.at_ledger_state)
+            .map(|ledger_state| ledger_state.state_version)
+            .or(self.max_ledger_state_version)
+    }
+}
+
+#[derive(Deserialize)]
+struct StateVersion {
+    state_version: u64,
+}
+
+impl LedgerGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The highest state version seen in a response so far.
+    pub fn highest(&self) -> Option<u64> {
+        match self.highest.load(Ordering::SeqCst) {
+            0 => None,
+            state_version => Some(state_version),
+        }
+    }
+
+    /// Raises the highest state version, e.g. when it is
+    /// known from a response of a different client.
+    pub fn observe(&self, state_version: u64) {
+        self.highest.fetch_max(state_version, Ordering::SeqCst);
+    }
+
+    /// Forgets the highest state version and any pin.
+    pub fn reset(&self) {
+        self.highest.store(0, Ordering::SeqCst);
+        self.unpin();
+    }
+
+    /// Sends every following request that has an unset `at_ledger_state`
+    /// at the highest state version seen so far. Returns that version,
+    /// or `None` when no response has been seen yet.
+    pub fn pin(&self) -> Option<u64> {
+        let highest = self.highest();
+        *self.pinned.lock().unwrap() = highest;
+        highest
+    }
+
+    pub fn unpin(&self) {
+        *self.pinned.lock().unwrap() = None;
+    }
+
+    pub fn pinned(&self) -> Option<u64> {
+        *self.pinned.lock().unwrap()
+    }
+
+    /// Sets `at_ledger_state` to the pinned state version,
+    /// if the request has the field but left it unset.
+    pub fn pin_request(&self, body: &mut serde_json::Value) {
+        let Some(state_version) = self.pinned() else {
+            return;
+        };
+        if let Some(at_ledger_state) = body
+            .as_object_mut()
+            .and_then(|body| body.get_mut("at_ledger_state"))
+            .filter(|at_ledger_state| at_ledger_state.is_null())
+        {
+            *at_ledger_state =
+                serde_json::json!({ "state_version": state_version });
+        }
+    }
+
+    /// Passes the result through, unless it is a successful response at
+    /// an older ledger state than one seen before. Responses without a
+    /// state version, and responses to a request `body` with an explicit
+    /// `at_ledger_state`, are passed through unchanged.
+    pub fn check<T>(
+        &self,
+        body: &serde_json::Value,
+        result: Result<(String, StatusCode, T), TransportError>,
+    ) -> Result<(String, StatusCode, T), TransportError> {
+        let (text, status, extra) = result?;
+        if !status.is_success() || reads_at_ledger_state(body) {
+            return Ok((text, status, extra));
+        }
+        let state_version = serde_json::from_str::<ResponseLedgerState>(&text)
+            .ok()
+            .and_then(ResponseLedgerState::state_version);
+        if let Some(state_version) = state_version {
+            let highest =
+                self.highest.fetch_max(state_version, Ordering::SeqCst);
+            if state_version < highest {
+                return Err(TransportError::StaleLedgerState {
+                    state_version,
+                    highest,
+                });
+            }
+        }
+        Ok((text, status, extra))
+    }
+}
+
+fn reads_at_ledger_state(body: &serde_json::Value) -> bool {
+    body.get("at_ledger_state")
+        .is_some_and(|at_ledger_state| !at_ledger_state.is_null())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stream::test_utils::transaction_page;
+    use crate::transport::{MockTransport, TransportBlocking};
+    use crate::{CoreClientBuilder, GatewayClientBuilder};
+    use serde_json::json;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    fn at(state_version: u64) -> String {
+        serde_json::json!({
+            "ledger_state": { "state_version": state_version }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn rejects_older_responses() {
+        let guard = LedgerGuard::new();
+        let body = json!({});
+        assert!(guard.check(&body, Ok((at(10), StatusCode::OK, ()))).is_ok());
+        assert!(guard.check(&body, Ok((at(10), StatusCode::OK, ()))).is_ok());
+        assert!(matches!(
+            guard.check(&body, Ok((at(9), StatusCode::OK, ()))),
+            Err(TransportError::StaleLedgerState {
+                state_version: 9,
+                highest: 10
+            })
+        ));
+        assert!(guard
+            .check(&body, Ok(("{}".into(), StatusCode::OK, ())))
+            .is_ok());
+        assert_eq!(guard.highest(), Some(10));
+    }
+
+    #[test]
+    fn passes_explicit_ledger_states_through() {
+        let guard = LedgerGuard::new();
+        guard.observe(10);
+        let body = json!({ "at_ledger_state": { "state_version": 4 } });
+        assert!(guard.check(&body, Ok((at(4), StatusCode::OK, ()))).is_ok());
+        let body = json!({ "at_ledger_state": { "state_version": 20 } });
+        assert!(guard.check(&body, Ok((at(20), StatusCode::OK, ()))).is_ok());
+        assert_eq!(guard.highest(), Some(10));
+    }
+
+    #[test]
+    fn pins_unset_ledger_states() {
+        let guard = LedgerGuard::new();
+        let mut body = serde_json::json!({ "at_ledger_state": null });
+        guard.pin_request(&mut body);
+        assert!(body["at_ledger_state"].is_null());
+
+        guard.observe(42);
+        assert_eq!(guard.pin(), Some(42));
+        guard.pin_request(&mut body);
+        assert_eq!(body["at_ledger_state"]["state_version"], 42);
+
+        let mut explicit = serde_json::json!({
+            "at_ledger_state": { "epoch": 7 }
+        });
+        guard.pin_request(&mut explicit);
+        assert_eq!(explicit["at_ledger_state"]["epoch"], 7);
+    }
+
+    #[test]
+    fn client_fails_over_from_a_lagging_gateway() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("status/gateway-status", &at(100));
+        mock.respond_ok("status/gateway-status", &at(90));
+        mock.respond_ok("status/gateway-status", &at(101));
+        mock.respond_ok("status/gateway-status", &at(95));
+        mock.respond_ok("status/gateway-status", &at(99));
+        let transport: Rc<dyn TransportBlocking> = mock.clone();
+        let guard = Arc::new(LedgerGuard::new());
+        let client = GatewayClientBuilder::new("http://own".to_string())
+            .fallback("http://public".to_string())
+            .transport_blocking(transport)
+            .ledger_guard(guard.clone())
+            .build_blocking()
+            .unwrap();
+
+        client.post("status/gateway-status", ()).unwrap();
+        let (text, _) = client.post("status/gateway-status", ()).unwrap();
+        assert_eq!(text, at(101));
+        assert_eq!(
+            mock.requests()[2].url,
+            "http://public/status/gateway-status"
+        );
+        // The lagging gateway is cooling down, and so is the only
+        // other one once it falls behind as well.
+        assert!(matches!(
+            client.post("status/gateway-status", ()),
+            Err(TransportError::StaleLedgerState { .. })
+        ));
+        assert_eq!(guard.highest(), Some(101));
+    }
+
+    #[test]
+    fn checks_core_responses() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond_ok("stream/transactions", &transaction_page(100, &[]));
+        mock.respond_ok(
+            "transaction/call-preview",
+            &json!({ "at_ledger_state": { "state_version": 90 } }).to_string(),
+        );
+        mock.respond_ok("mempool/list", r#"{"contents":[]}"#);
+        let transport: Rc<dyn TransportBlocking> = mock.clone();
+        let guard = Arc::new(LedgerGuard::new());
+        let client = CoreClientBuilder::new("http://own".to_string())
+            .transport_blocking(transport)
+            .ledger_guard(guard.clone())
+            .build_blocking()
+            .unwrap();
+
+        client.post("stream/transactions", json!({})).unwrap();
+        assert_eq!(guard.highest(), Some(100));
+        assert!(matches!(
+            client.post("transaction/call-preview", json!({})),
+            Err(TransportError::StaleLedgerState {
+                state_version: 90,
+                highest: 100
+            })
+        ));
+        assert!(client.post("mempool/list", json!({})).is_ok());
+    }
+}
@@ -1,10 +1,22 @@
+pub mod auth;
+pub mod cassette;
+pub mod checkpoint;
 pub mod client;
 pub mod constants;
 #[cfg(feature = "core")]
 pub mod core;
 pub mod deserialize;
+pub mod failover;
 #[cfg(feature = "gateway")]
 pub mod gateway;
+pub mod ledger_guard;
+pub mod metrics;
+pub mod rate_limit;
+pub mod retry;
+pub mod stream_config;
+pub mod transport;
 
+#[cfg(feature = "core")]
 pub use client::core::*;
+#[cfg(feature = "gateway")]
 pub use client::gateway::*;
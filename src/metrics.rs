@@ -0,0 +1,204 @@
+//! # Metrics
+//!
+//! Optional hook that is told about every request a client makes, for
+//! dashboards of Gateway and Core API health. Implement [`MetricsHook`] to
+//! forward the numbers to a metrics library, or use [`InMemoryMetrics`] to
+//! keep them in memory.
+//!
+//! Independent of the hook, every request runs in a `tracing` span named
+//! `request`, with the endpoint path, status code, latency, response size,
+//! number of retries and the base URL that served it as fields.
+
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A request as seen by `post`, after all retries.
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    pub path: String,
+    /// Not set when no response was received.
+    pub status: Option<StatusCode>,
+    /// Including retries and the time spent waiting in between.
+    pub latency: Duration,
+    /// Size of the response body in bytes.
+    pub response_size: usize,
+    pub retries: u32,
+    /// The base URL that served the request, redacted like in `Debug`
+    /// output. Differs from the client's when it failed over.
+    pub base_url: Option<String>,
+}
+
+pub trait MetricsHook: Debug + Send + Sync {
+    /// Called once for every request, successful or not.
+    fn on_request(&self, _request: &RequestMetrics) {}
+
+    /// Called for every failed API call with the name of the error
+    /// variant, e.g. `ServerError` for a
+    /// `GatewayApiError::ServerError`.
+    fn on_error(&self, _path: &str, _variant: &'static str) {}
+}
+
+/// Gives the name of an error variant for the [`MetricsHook`].
+pub trait ErrorVariant {
+    fn variant(&self) -> &'static str;
+}
+
+/// Transport errors reach the caller as the `Network` or `Transport`
+/// variant of `GatewayApiError` and `CoreApiError`.
+impl ErrorVariant for TransportError {
+    fn variant(&self) -> &'static str {
+        match self {
+            TransportError::Network(_) => "Network",
+            _ => "Transport",
+        }
+    }
+}
+
+/// Counters and latencies per endpoint path, kept in memory.
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    endpoints: Mutex<HashMap<String, EndpointMetrics>>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EndpointMetrics {
+    pub requests: u64,
+    pub retries: u64,
+    pub statuses: HashMap<u16, u64>,
+    /// Failed API calls per error variant.
+    pub errors: HashMap<&'static str, u64>,
+    pub latency: LatencyHistogram,
+    pub response_bytes: u64,
+}
+
+/// Upper bounds of the latency buckets, in milliseconds.
+pub const LATENCY_BUCKETS_MS: [u64; 10] =
+    [10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Latencies in fixed buckets, so memory use does not grow with the
+/// number of requests.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    pub count: u64,
+    pub sum: Duration,
+    pub min: Option<Duration>,
+    pub max: Option<Duration>,
+    /// Requests per bucket of [`LATENCY_BUCKETS_MS`], followed by the
+    /// requests that took longer than the last bucket.
+    pub buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        self.sum += latency;
+        self.min = Some(self.min.map_or(latency, |min| min.min(latency)));
+        self.max = Some(self.max.map_or(latency, |max| max.max(latency)));
+        let millis = latency.as_millis();
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound as u128)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.sum / self.count as u32)
+    }
+}
+
+impl InMemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn endpoint(&self, path: &str) -> EndpointMetrics {
+        self.endpoints
+            .lock()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, EndpointMetrics> {
+        self.endpoints.lock().unwrap().clone()
+    }
+}
+
+impl MetricsHook for InMemoryMetrics {
+    fn on_request(&self, request: &RequestMetrics) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let endpoint = endpoints.entry(request.path.clone()).or_default();
+        endpoint.requests += 1;
+        endpoint.retries += request.retries as u64;
+        if let Some(status) = request.status {
+            *endpoint.statuses.entry(status.as_u16()).or_default() += 1;
+        }
+        endpoint.latency.record(request.latency);
+        endpoint.response_bytes += request.response_size as u64;
+    }
+
+    fn on_error(&self, path: &str, variant: &'static str) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let endpoint = endpoints.entry(path.to_string()).or_default();
+        *endpoint.errors.entry(variant).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use crate::transport::MockTransport;
+    use crate::GatewayClientBuilder;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn records_requests_and_error_variants() {
+        let mock = Rc::new(MockTransport::new());
+        mock.respond("status/gateway-status", StatusCode::BAD_GATEWAY, "");
+        mock.respond_ok("status/gateway-status", "not json");
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let client = GatewayClientBuilder::new("http://mock".to_string())
+            .transport_blocking(mock)
+            .retry_policy(
+                RetryPolicy::new(2).initial_backoff(Duration::from_millis(1)),
+            )
+            .metrics(metrics.clone())
+            .build_blocking()
+            .unwrap();
+        assert!(client.gateway_status().is_err());
+        // No responses are left, so this fails in the transport.
+        assert!(client.gateway_status().is_err());
+
+        let endpoint = metrics.endpoint("status/gateway-status");
+        assert_eq!(endpoint.requests, 2);
+        assert_eq!(endpoint.retries, 1);
+        assert_eq!(endpoint.statuses[&200], 1);
+        assert_eq!(endpoint.errors["Parsing"], 1);
+        assert_eq!(endpoint.errors["Transport"], 1);
+        assert_eq!(endpoint.latency.count, 2);
+    }
+
+    #[test]
+    fn latencies_are_bucketed() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.mean(), None);
+        for millis in [5, 30, 30, 20_000] {
+            histogram.record(Duration::from_millis(millis));
+        }
+        assert_eq!(histogram.count, 4);
+        assert_eq!(histogram.min, Some(Duration::from_millis(5)));
+        assert_eq!(histogram.max, Some(Duration::from_millis(20_000)));
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(5_016_250)));
+        assert_eq!(histogram.buckets[0], 1);
+        assert_eq!(histogram.buckets[2], 2);
+        assert_eq!(histogram.buckets[LATENCY_BUCKETS_MS.len()], 1);
+    }
+}
@@ -0,0 +1,199 @@
+//! # Rate limit
+//!
+//! Client-side token bucket limiter. Clones of a client share the same
+//! limiter, so the configured rate applies to all of them together.
+//! The limiter only computes how long a request has to wait; the clients
+//! do the actual waiting, which keeps the async clients off the executor.
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::error::Error;
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often a single request is retried after a `Retry-After`
+/// before the 429 response is returned to the caller.
+pub const MAX_RETRY_AFTER_WAITS: u32 = 5;
+
+/// The longest `Retry-After` a client waits for by default. A 429
+/// response asking for a longer wait is returned to the caller instead.
+pub const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Why a rate limiter could not be created.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitError {
+    /// The rate is zero, negative or not a number.
+    InvalidRate(f64),
+}
+
+impl Display for RateLimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RateLimitError::InvalidRate(rate) => write!(
+                f,
+                "requests_per_second must be greater than 0, got {}",
+                rate
+            ),
+        }
+    }
+}
+
+impl Error for RateLimitError {}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    max_retry_after: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_second` requests on average, with bursts of
+    /// at most one second worth of requests.
+    pub fn new(requests_per_second: f64) -> Result<Self, RateLimitError> {
+        if !(requests_per_second > 0.0 && requests_per_second.is_finite()) {
+            return Err(RateLimitError::InvalidRate(requests_per_second));
+        }
+        let burst = requests_per_second.max(1.0);
+        Ok(RateLimiter {
+            requests_per_second,
+            burst,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        })
+    }
+
+    /// The maximum amount of requests that can be sent at once
+    /// after a quiet period.
+    pub fn burst(mut self, value: u32) -> Self {
+        self.burst = value.max(1) as f64;
+        self.bucket.get_mut().unwrap().tokens = self.burst;
+        self
+    }
+
+    /// The longest `Retry-After` the client waits for. A 429 response
+    /// asking for a longer wait is returned to the caller instead.
+    pub fn max_retry_after(mut self, value: Duration) -> Self {
+        self.max_retry_after = value;
+        self
+    }
+
+    pub fn requests_per_second(&self) -> f64 {
+        self.requests_per_second
+    }
+
+    /// Takes a token and returns how long the caller has to wait
+    /// before it may send its request.
+    pub fn acquire(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second)
+            .min(self.burst);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+
+        let paused = bucket
+            .paused_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+        let throttled = if bucket.tokens < 0.0 {
+            Duration::from_secs_f64(-bucket.tokens / self.requests_per_second)
+        } else {
+            Duration::ZERO
+        };
+        paused.max(throttled)
+    }
+
+    /// Stops handing out tokens without delay until the given time has
+    /// passed. Used when the server responds with a `Retry-After`.
+    /// Returns `false` when the wait exceeds `max_retry_after`.
+    pub fn pause_for(&self, value: Duration) -> bool {
+        if value > self.max_retry_after {
+            return false;
+        }
+        let mut bucket = self.bucket.lock().unwrap();
+        let until = Instant::now() + value;
+        bucket.paused_until = bucket.paused_until.max(Some(until));
+        true
+    }
+}
+
+/// Reads a `Retry-After` header, which is either a number
+/// of seconds or an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn bucket_throttles_after_the_burst() {
+        let limiter = RateLimiter::new(10.0).unwrap().burst(2);
+        assert_eq!(limiter.acquire(), Duration::ZERO);
+        assert_eq!(limiter.acquire(), Duration::ZERO);
+        let wait = limiter.acquire();
+        assert!(wait > Duration::from_millis(90));
+        assert!(wait <= Duration::from_millis(100));
+        // Waiting callers queue up behind each other.
+        assert!(limiter.acquire() > Duration::from_millis(190));
+    }
+
+    #[test]
+    fn pause_delays_every_request() {
+        let limiter = RateLimiter::new(100.0).unwrap();
+        assert!(limiter.pause_for(Duration::from_secs(5)));
+        assert!(limiter.acquire() > Duration::from_secs(4));
+        assert!(!limiter.pause_for(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::INFINITY] {
+            assert_eq!(
+                RateLimiter::new(rate).unwrap_err(),
+                RateLimitError::InvalidRate(rate)
+            );
+        }
+        assert!(RateLimiter::new(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn parses_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
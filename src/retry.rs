@@ -0,0 +1,198 @@
+//! # Retry
+//!
+//! Opt-in retry policy for the Gateway and Core clients. A policy is set on
+//! the client through its builder, after which every endpoint retries
+//! transient failures with exponential backoff.
+
+use crate::transport::TransportError;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Decides which failed requests are retried and how long to wait
+/// between attempts. The delay doubles with every attempt, starting at
+/// `initial_backoff` and capped at `max_backoff`. With jitter enabled,
+/// a random delay between zero and that value is used instead.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+    /// Retry when the connection could not be made or timed out.
+    pub retry_network_errors: bool,
+    /// Retry when the response has one of these status codes.
+    pub retry_statuses: Vec<StatusCode>,
+    /// Retry when the Gateway reports that it is not synced up.
+    pub retry_not_synced_up: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+            jitter: true,
+            retry_network_errors: true,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_not_synced_up: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    pub fn initial_backoff(mut self, value: Duration) -> Self {
+        self.initial_backoff = value;
+        self
+    }
+
+    pub fn max_backoff(mut self, value: Duration) -> Self {
+        self.max_backoff = value;
+        self
+    }
+
+    pub fn without_jitter(mut self) -> Self {
+        self.jitter = false;
+        self
+    }
+
+    pub fn retry_network_errors(mut self, value: bool) -> Self {
+        self.retry_network_errors = value;
+        self
+    }
+
+    pub fn retry_statuses(mut self, value: Vec<StatusCode>) -> Self {
+        self.retry_statuses = value;
+        self
+    }
+
+    pub fn retry_not_synced_up(mut self, value: bool) -> Self {
+        self.retry_not_synced_up = value;
+        self
+    }
+
+    /// The delay before the next attempt, where `attempt` is the
+    /// number of the attempt that just failed (starting at 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if self.jitter {
+            delay.mul_f64(fastrand::f64())
+        } else {
+            delay
+        }
+    }
+
+    pub fn is_retriable_error(&self, error: &TransportError) -> bool {
+        match error {
+            TransportError::Network(error) => {
+                self.retry_network_errors
+                    && (error.is_connect() || error.is_timeout())
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_retriable_response(
+        &self,
+        status: StatusCode,
+        body: &str,
+    ) -> bool {
+        if self.retry_statuses.contains(&status) {
+            return true;
+        }
+        self.retry_not_synced_up
+            && !status.is_success()
+            && is_not_synced_up(body)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    details: Option<ErrorBodyDetails>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBodyDetails {
+    r#type: String,
+}
+
+/// Checks the `details.type` of an error response without
+/// requiring the full Gateway error model.
+pub(crate) fn is_not_synced_up(body: &str) -> bool {
+    serde_json::from_str::<ErrorBody>(body)
+        .ok()
+        .and_then(|body| body.details)
+        .map(|details| details.r#type == "NotSyncedUpError")
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_the_cap() {
+        let policy = RetryPolicy::new(10)
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .without_jitter();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn jitter_stays_below_the_backoff() {
+        let policy =
+            RetryPolicy::new(3).initial_backoff(Duration::from_secs(1));
+        for _ in 0..100 {
+            assert!(policy.backoff(1) <= Duration::from_secs(1));
+        }
+    }
+
+    #[test]
+    fn classifies_responses() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retriable_response(StatusCode::BAD_GATEWAY, ""));
+        assert!(policy.is_retriable_response(StatusCode::TOO_MANY_REQUESTS, ""));
+        assert!(!policy.is_retriable_response(StatusCode::BAD_REQUEST, "{}"));
+        let not_synced_up = r#"{
+            "message": "Not synced up",
+            "code": 500,
+            "details": {
+                "type": "NotSyncedUpError",
+                "request_type": "Transaction",
+                "current_sync_delay_seconds": 120,
+                "max_allowed_sync_delay_seconds": 60
+            }
+        }"#;
+        assert!(policy.is_retriable_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            not_synced_up
+        ));
+        assert!(!policy.retry_not_synced_up(false).is_retriable_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            not_synced_up
+        ));
+    }
+}
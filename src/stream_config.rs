@@ -0,0 +1,119 @@
+//! Shared by the transaction streams of the Gateway and Core clients.
+
+use std::error::Error;
+use std::fmt::Display;
+use std::time::Duration;
+
+/// The longest a stream waits before polling again after errors.
+pub const MAX_ERROR_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Why a transaction stream could not be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamConfigError {
+    /// State versions start at 1.
+    ZeroFromStateVersion,
+    ZeroLimitPerPage,
+    LimitPerPageTooLarge {
+        limit: u32,
+        max: u32,
+    },
+    /// The stream starts further behind the ledger tip than it may.
+    TooFarBehind {
+        from_state_version: u64,
+        ledger_tip: u64,
+        max_behind: u64,
+    },
+    /// A backfill ends before it starts.
+    InvertedRange {
+        from_state_version: u64,
+        to_state_version: u64,
+    },
+}
+
+impl Display for StreamConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StreamConfigError::ZeroFromStateVersion => {
+                write!(f, "from_state_version must be greater than 0")
+            }
+            StreamConfigError::ZeroLimitPerPage => {
+                write!(f, "limit_per_page must be greater than 0")
+            }
+            StreamConfigError::LimitPerPageTooLarge { limit, max } => write!(
+                f,
+                "limit_per_page is {}, the API returns at most {}",
+                limit, max
+            ),
+            StreamConfigError::TooFarBehind {
+                from_state_version,
+                ledger_tip,
+                max_behind,
+            } => write!(
+                f,
+                "from_state_version {} is more than {} behind the \
+                 ledger tip at {}",
+                from_state_version, max_behind, ledger_tip
+            ),
+            StreamConfigError::InvertedRange {
+                from_state_version,
+                to_state_version,
+            } => write!(
+                f,
+                "from_state_version {} is greater than to_state_version {}",
+                from_state_version, to_state_version
+            ),
+        }
+    }
+}
+
+impl Error for StreamConfigError {}
+
+/// Checks that a stream starts at a state version of at least 1 and
+/// asks for between 1 and `max_limit_per_page` transactions per page.
+pub fn validate(
+    from_state_version: u64,
+    limit_per_page: u32,
+    max_limit_per_page: u32,
+) -> Result<(), StreamConfigError> {
+    if from_state_version == 0 {
+        return Err(StreamConfigError::ZeroFromStateVersion);
+    }
+    if limit_per_page == 0 {
+        return Err(StreamConfigError::ZeroLimitPerPage);
+    }
+    if limit_per_page > max_limit_per_page {
+        return Err(StreamConfigError::LimitPerPageTooLarge {
+            limit: limit_per_page,
+            max: max_limit_per_page,
+        });
+    }
+    Ok(())
+}
+
+/// How long a stream, used as a `Stream` or `Iterator`, waits before
+/// polling again after `consecutive_errors` failed polls in a row: its
+/// poll `interval`, doubled with every further error, up to
+/// [`MAX_ERROR_BACKOFF`]. `None` when the last poll did not fail.
+pub fn error_backoff(
+    interval: Duration,
+    consecutive_errors: u32,
+) -> Option<Duration> {
+    let doublings = consecutive_errors.checked_sub(1)?;
+    let factor = 1u32.checked_shl(doublings).unwrap_or(u32::MAX);
+    Some(interval.saturating_mul(factor).min(MAX_ERROR_BACKOFF))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially_after_errors() {
+        let interval = Duration::from_secs(1);
+        assert_eq!(error_backoff(interval, 0), None);
+        assert_eq!(error_backoff(interval, 1), Some(interval));
+        assert_eq!(error_backoff(interval, 3), Some(interval * 4));
+        assert_eq!(error_backoff(interval, 7), Some(MAX_ERROR_BACKOFF));
+        assert_eq!(error_backoff(interval, 40), Some(MAX_ERROR_BACKOFF));
+    }
+}
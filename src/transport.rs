@@ -0,0 +1,246 @@
+//! # Transport
+//!
+//! The HTTP layer underneath the clients. Every endpoint ends up in the
+//! client's `post` method, which hands a [`HttpRequest`] to a transport.
+//! By default this is the `reqwest` client, but any implementation of
+//! [`TransportAsync`] or [`TransportBlocking`] can be passed to the client
+//! builders, for example the in-memory [`MockTransport`] for tests.
+
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub url: String,
+    /// The endpoint path relative to the base url,
+    /// e.g. `stream/transactions`.
+    pub path: String,
+    pub headers: HeaderMap,
+    /// The JSON encoded request body.
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+pub enum TransportError {
+    Network(reqwest::Error),
+    Serialization(serde_json::Error),
+    /// The response was served at an older ledger state than a previous
+    /// one, see [`LedgerGuard`](crate::ledger_guard::LedgerGuard).
+    StaleLedgerState {
+        state_version: u64,
+        highest: u64,
+    },
+    Other(String),
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TransportError::Network(e) => write!(f, "Network error: {}", e),
+            TransportError::Serialization(e) => {
+                write!(f, "Could not serialize request: {}", e)
+            }
+            TransportError::StaleLedgerState {
+                state_version,
+                highest,
+            } => write!(
+                f,
+                "Stale ledger state: response at state version {} \
+                 after seeing {}",
+                state_version, highest
+            ),
+            TransportError::Other(e) => write!(f, "Transport error: {}", e),
+        }
+    }
+}
+
+impl Debug for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Use the Display implementation
+        write!(f, "{}", self)
+    }
+}
+
+impl Error for TransportError {}
+
+// The URL is dropped, some providers put the API key in it.
+impl From<reqwest::Error> for TransportError {
+    fn from(e: reqwest::Error) -> Self {
+        TransportError::Network(e.without_url())
+    }
+}
+
+impl From<serde_json::Error> for TransportError {
+    fn from(e: serde_json::Error) -> Self {
+        TransportError::Serialization(e)
+    }
+}
+
+/// Sends requests for the async clients.
+pub trait TransportAsync: Debug + Send + Sync {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<HttpResponse, TransportError>>;
+}
+
+/// Sends requests for the blocking clients.
+pub trait TransportBlocking: Debug {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError>;
+}
+
+impl TransportAsync for reqwest::Client {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let res = self
+                .post(request.url)
+                .headers(request.headers)
+                .body(request.body)
+                .send()
+                .await?;
+            let status = res.status();
+            let headers = res.headers().clone();
+            let body = res.text().await?;
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+impl TransportBlocking for reqwest::blocking::Client {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        let res = self
+            .post(request.url)
+            .headers(request.headers)
+            .body(request.body)
+            .send()?;
+        let status = res.status();
+        let headers = res.headers().clone();
+        let body = res.text()?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Headers that are sent with every request, on top of the
+/// default headers of the transport.
+pub(crate) fn json_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, "application/json".parse().unwrap());
+    headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    headers
+}
+
+/// In-memory transport that serves canned responses, for testing code
+/// built on the clients without network access. Responses are queued per
+/// endpoint path and served in order. A request for a path without queued
+/// responses fails with a [`TransportError::Other`].
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    responses: Mutex<HashMap<String, VecDeque<HttpResponse>>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a response for the given endpoint path,
+    /// e.g. `stream/transactions`.
+    pub fn respond(&self, path: &str, status: StatusCode, body: &str) {
+        self.respond_with(
+            path,
+            HttpResponse {
+                status,
+                headers: HeaderMap::new(),
+                body: body.to_string(),
+            },
+        )
+    }
+
+    /// Queues a full response, including headers, for the given path.
+    pub fn respond_with(&self, path: &str, response: HttpResponse) {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(path.trim_matches('/').to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Queues a `200 OK` response for the given endpoint path.
+    pub fn respond_ok(&self, path: &str, body: &str) {
+        self.respond(path, StatusCode::OK, body)
+    }
+
+    /// All requests that were sent through this transport, in order.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn handle(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        self.requests.lock().unwrap().push(request.clone());
+        let mut responses = self.responses.lock().unwrap();
+        responses
+            .get_mut(&request.path)
+            .and_then(|queue| queue.pop_front())
+            .ok_or_else(|| {
+                TransportError::Other(format!(
+                    "No mock response left for {}",
+                    request.url
+                ))
+            })
+    }
+}
+
+impl TransportAsync for MockTransport {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        let response = self.handle(request);
+        Box::pin(async move { response })
+    }
+}
+
+impl TransportBlocking for MockTransport {
+    fn send(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, TransportError> {
+        self.handle(request)
+    }
+}